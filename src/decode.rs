@@ -0,0 +1,101 @@
+/*
+Decoders of payloads that are often met in logs: url-encoded query params, hex dumps and base64.
+Only text is useful for further parsing, so a decoder fails if the result is not valid utf-8 or
+contains control characters
+*/
+
+type Decoder = fn(&str) -> Option<String>;
+
+/// (name, decoder). Hex goes before base64: every hex string is valid base64 too
+pub(crate) const DECODERS: [(&str, Decoder); 3] = [("url", url_decode), ("hex", hex_decode), ("base64", base64_decode)];
+
+fn text(bytes: Vec<u8>) -> Option<String> {
+    let s = String::from_utf8(bytes).ok()?;
+    let is_text = s.chars().all(|c| !c.is_control() || c.is_whitespace());
+    is_text.then_some(s)
+}
+
+fn hex_byte(hex: &[u8]) -> Option<u8> {
+    let digit = |c: u8| (c as char).to_digit(16);
+    Some((digit(hex[0])? * 16 + digit(hex[1])?) as u8)
+}
+
+// %7B%22a%22%3A1%7D
+fn url_decode(s: &str) -> Option<String> {
+    if !s.contains('%') {
+        return None;
+    }
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            res.push(hex_byte(bytes.get(i + 1..i + 3)?)?);
+            i += 3;
+        }
+        else {
+            res.push(bytes[i]);
+            i += 1;
+        }
+    }
+    text(res)
+}
+
+// 7b2261223a317d
+fn hex_decode(s: &str) -> Option<String> {
+    if s.len() % 2 == 1 {
+        return None;
+    }
+    text(s.as_bytes().chunks(2).map(hex_byte).collect::<Option<Vec<_>>>()?)
+}
+
+// eyJhIjoxfQ==, the padding is optional. Url-safe alphabet (-_ instead of +/) is accepted too
+fn base64_decode(s: &str) -> Option<String> {
+    let s = s.trim_end_matches('=');
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut res = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let n = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | n as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    text(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(url_decode("%7B%22a%22%3A%201%7D"), Some(r#"{"a": 1}"#.to_string()));
+        assert_eq!(url_decode("a%2"), None);
+        assert_eq!(url_decode("%+f"), None);
+        assert_eq!(url_decode("abc"), None);
+        assert_eq!(hex_decode("7b2261223a317d"), Some(r#"{"a":1}"#.to_string()));
+        assert_eq!(hex_decode("7b2"), None);
+        assert_eq!(hex_decode("00ff"), None);
+        assert_eq!(base64_decode("eyJhIjoxfQ=="), Some(r#"{"a":1}"#.to_string()));
+        assert_eq!(base64_decode("eyJhIjoxfQ"), Some(r#"{"a":1}"#.to_string()));
+        assert_eq!(base64_decode("W3siYSI6ICJiIn1d"), Some(r#"[{"a": "b"}]"#.to_string()));
+        assert_eq!(base64_decode("PD94bWw_"), Some("<?xml?".to_string()));
+        assert_eq!(base64_decode("abcde"), None);
+        assert_eq!(base64_decode("a!bc"), None);
+    }
+}
@@ -58,6 +58,7 @@ impl PartialOrd for CharPosition {
     }
 }
 
+/// Iterator over (position, chunk) of everything in the input that looks like a balanced bracket sequence
 pub struct DetectDataIter<'a> {
     input: &'a [char],
     start: usize,
@@ -93,8 +94,119 @@ pub fn get_open(c: char) -> char {
     }
 }
 
-#[allow(dead_code)]
-fn get_close(c: char) -> char {
+// if a comment starts at `idx` - returns the position right after the comment
+// "//" and "# " comments last to the end of line, "/* */" to the closing mark
+// a comment can only start a token, so "http://" is not a comment. "//cdn.example.com/x.js" is a
+// protocol-relative url, not a comment either
+pub fn skip_comment(input: &[char], idx: usize) -> Option<usize> {
+    if idx > 0 && !(input[idx - 1].is_whitespace() || "{[(,;".contains(input[idx - 1])) {
+        return None;
+    }
+    if input[idx..].starts_with(&['/', '/']) && input.get(idx + 2).is_some_and(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    let find = |from: usize, pat: &[char]| {
+        (from..input.len().saturating_sub(pat.len() - 1)).find(|i| input[*i..].starts_with(pat))
+    };
+    match (input[idx], input.get(idx + 1)) {
+        ('/', Some('/')) | ('#', Some(' ' | '\t')) => Some(find(idx, &['\n']).unwrap_or(input.len())),
+        ('/', Some('*')) => find(idx + 2, &['*', '/']).map(|i| i + 2),
+        _ => None,
+    }
+}
+
+// tracks string literals while the input is scanned char by char, so brackets inside of strings
+// are not counted. Knows about escapes, python triple-quoted strings: '''it's''', rust raw
+// strings: r#"a "b" c"# and js template literals: `a ${b} c`
+#[derive(Debug, Default)]
+pub struct StringTracker {
+    // the closing quote of the current string
+    close:  Option<Vec<char>>,
+    // backslash escapes are not processed in rust raw strings
+    raw:    bool,
+    escape: bool,
+    // chars of a closing quote that are already handled
+    skip:   usize,
+    // nesting depth of braces in ${...} of a template literal
+    braces: usize,
+    // the input is cut, a template literal may have no closing backtick
+    cut:    bool,
+}
+
+impl StringTracker {
+    pub fn cut() -> Self {
+        Self {
+            cut: true,
+            ..Self::default()
+        }
+    }
+
+    // feed the char at `idx`; returns true if the char is a part of a string, including the quotes
+    pub fn skip(&mut self, input: &[char], idx: usize) -> bool {
+        let c = input[idx];
+        if self.skip > 0 {
+            self.skip -= 1;
+            return true;
+        }
+        if self.escape {
+            self.escape = false;
+            return true;
+        }
+        match self.close {
+            Some(_) if c == '\\' && !self.raw => self.escape = true,
+            Some(ref close) if close == &['`'] && (self.braces > 0 || c == '$') => match c {
+                '$' if input.get(idx + 1) == Some(&'{') => {
+                    self.braces += 1;
+                    self.skip = 1;
+                }
+                '{' => self.braces += 1,
+                '}' => self.braces -= 1,
+                _ => {}
+            },
+            Some(ref close) if input[idx..].starts_with(close) => {
+                self.skip = close.len() - 1;
+                self.close = None;
+            }
+            Some(_) => {}
+            // a lone backtick in prose is not a template literal
+            None if c == '`' && (self.cut || input[idx + 1..].contains(&'`')) => {
+                self.raw = false;
+                self.close = Some(vec![c]);
+            }
+            None if c == '\'' || c == '"' => {
+                let hashes = input[..idx].iter().rev().take_while(|c| **c == '#').count();
+                self.raw = c == '"' && hashes > 0 && idx > hashes && input[idx - hashes - 1] == 'r';
+                let close = if self.raw {
+                    [vec![c], vec!['#'; hashes]].concat()
+                }
+                else if input[idx..].iter().take(3).all(|x| *x == c) {
+                    self.skip = 2;
+                    vec![c; 3]
+                }
+                else {
+                    vec![c]
+                };
+                self.close = Some(close);
+            }
+            None => return false,
+        }
+        true
+    }
+
+    // the closing quote of a string that is left open, with the braces of an open ${...}
+    pub fn open_quote(&self) -> Option<String> {
+        self.close
+            .as_ref()
+            .map(|close| "}".repeat(self.braces) + &String::from_iter(close))
+    }
+
+    // the last char was a backslash inside of a string
+    pub fn is_escape(&self) -> bool {
+        self.escape
+    }
+}
+
+pub fn get_close(c: char) -> char {
     match c {
         '(' => ')',
         '[' => ']',
@@ -119,34 +231,26 @@ impl<'a> Iterator for DetectDataIter<'a> {
         let mut all_cnt = 0;
         // signature and position of signature
         let mut sign_pos = HashMap::<(isize, isize, char), usize>::new(); // (cnt, all_cnt, open_char) => pos
-        let mut escape = false;
-        let mut str_char: Option<char> = None;
+        let mut strings = StringTracker::default();
+        // brackets inside of comments are skipped. Comments are only looked for inside of data
+        let mut comment_end = 0;
         // trace!("begin {}", self.start);
 
         for (idx, c) in self.input[self.start..].iter().enumerate() {
             let idx = idx + self.start;
             let c = *c;
-            if str_char.is_some() && c == '\\' {
-                escape = true;
+            if idx < comment_end {
                 continue;
             }
-            if escape {
-                escape = false;
+            if strings.skip(self.input, idx) {
                 continue;
             }
-            if c == '\'' || c == '"' {
-                if let Some(sc) = str_char {
-                    if sc == c {
-                        str_char = None;
-                    }
-                }
-                else {
-                    str_char = Some(c)
+            if all_cnt > 0 {
+                if let Some(end) = skip_comment(self.input, idx) {
+                    comment_end = end;
+                    continue;
                 }
             }
-            if str_char.is_some() {
-                continue;
-            }
             if is_open(c) {
                 let cnt = cnt_each.entry(c).or_insert(0);
                 sign_pos.insert((*cnt, all_cnt, c), idx);
@@ -184,6 +288,8 @@ impl<'a> Iterator for DetectDataIter<'a> {
 }
 
 #[cfg(test)]
+// the dumb detector is kept for manual checks, see test_dumb
+#[allow(dead_code, unused_imports)]
 mod test {
     fn init() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -197,6 +303,7 @@ mod test {
     use super::*;
 
     #[test]
+    #[allow(clippy::unused_unit)]
     fn test_detect() -> () {
         init();
         let cases = vec![
@@ -222,6 +329,17 @@ mod test {
             // ("[1, 2, 3] {[} (4, 5, 6) ]", vec![(0, "[1, 2, 3]"), (14, "(4, 5, 6)")]),
             (r#"[ "\"]" ]"#, vec![(0, r#"[ "\"]" ]"#)]),
             (r#"[[ "\"]" ]"#, vec![(1, r#"[ "\"]" ]"#)]),
+            ("{a: 1, // }\n}", vec![(0, "{a: 1, // }\n}")]),
+            ("{a: /* } */ 1}", vec![(0, "{a: /* } */ 1}")]),
+            ("[1, # ]\n 2]", vec![(0, "[1, # ]\n 2]")]),
+            ("{url: http://a/[b]}", vec![(0, "{url: http://a/[b]}")]),
+            ("// [1]", vec![(3, "[1]")]),
+            (r#"["\\", "]"]"#, vec![(0, r#"["\\", "]"]"#)]),
+            ("['''it's [''']", vec![(0, "['''it's [''']")]),
+            ("['', ']']", vec![(0, "['', ']']")]),
+            (r##"[r#"a "]" \"#]"##, vec![(0, r##"[r#"a "]" \"#]"##)]),
+            ("[`a ${ {b: `]`}.b } }`]", vec![(0, "[`a ${ {b: `]`}.b } }`]")]),
+            ("Text with `backtick [1, 2] unclosed", vec![(20, "[1, 2]")]),
         ];
         for (t, res) in cases {
             let input = t.chars().collect::<Vec<_>>();
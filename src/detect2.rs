@@ -1,10 +1,11 @@
 use std::collections::VecDeque;
 
 use crate::{
-    detect::{get_open, is_close, is_open},
+    detect::{get_open, is_close, is_open, skip_comment, StringTracker},
     stopwatch::Stopwatch,
 };
 
+/// Slower, but more accurate detector: yields only properly nested bracket sequences
 pub struct DetectDataV2<'a> {
     input: &'a [char],
     start: usize,
@@ -18,36 +19,28 @@ impl<'a> DetectDataV2<'a> {
         }
     }
 
-    fn next_char(&mut self, idx: usize) -> Option<(usize, char)> {
+    fn next_char(&mut self, idx: usize, in_data: bool) -> Option<(usize, char)> {
         // move to the next char, skipping all non-relevant chars
         // returns index of next char and the char itself
-        let mut str_char: Option<char> = None;
-        let mut escape = false;
+        // comments are only skipped inside of data (`in_data`)
+        let mut strings = StringTracker::default();
+        let mut comment_end = 0;
         for i in idx..self.input.len() {
             let a = self.input[i];
-
-            if str_char.is_some() && a == '\\' {
-                escape = true;
+            if i < comment_end {
                 continue;
             }
-            if escape {
-                escape = false;
+
+            if strings.skip(self.input, i) {
                 continue;
             }
 
-            if a == '\'' || a == '"' {
-                if let Some(sc) = str_char {
-                    if sc == a {
-                        str_char = None;
-                    }
-                }
-                else {
-                    str_char = Some(a)
+            if in_data {
+                if let Some(end) = skip_comment(self.input, i) {
+                    comment_end = end;
+                    continue;
                 }
             }
-            if str_char.is_some() {
-                continue;
-            }
 
             if a == '>' && i > 0 && self.input[i - 1] == '=' {
                 // hack for =>
@@ -73,12 +66,9 @@ impl<'a> Iterator for DetectDataV2<'a> {
         let mut i = self.start;
 
         'start: while i < self.input.len() {
-            let next = self.next_char(i);
+            let next = self.next_char(i, false);
             // trace!("i={} => {:?}", i, next);
-            if next.is_none() {
-                return None;
-            }
-            let (next_i, a) = next.unwrap();
+            let (next_i, a) = next?;
             i = next_i;
 
             if !is_open(a) {
@@ -89,7 +79,7 @@ impl<'a> Iterator for DetectDataV2<'a> {
             stack.push_back(a);
             let mut j = i + 1;
             while j < self.input.len() {
-                let next = self.next_char(j);
+                let next = self.next_char(j, true);
                 // trace!("j={} => {:?}", j, next);
                 if next.is_none() {
                     break;
@@ -127,6 +117,7 @@ impl<'a> Iterator for DetectDataV2<'a> {
 }
 
 #[cfg(test)]
+#[allow(unused_imports)]
 mod test {
     fn init() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -140,6 +131,7 @@ mod test {
     use super::*;
 
     #[test]
+    #[allow(clippy::unused_unit)]
     fn test_detect() -> () {
         init();
         let cases = vec![
@@ -164,6 +156,12 @@ mod test {
             ("[1, 2, 3] {[} (4, 5, 6) ]", vec![(0, "[1, 2, 3]"), (14, "(4, 5, 6)")]),
             (r#"[ "\"]" ]"#, vec![(0, r#"[ "\"]" ]"#)]),
             (r#"[[ "\"]" ]"#, vec![(1, r#"[ "\"]" ]"#)]),
+            ("{a: 1, // }\n}", vec![(0, "{a: 1, // }\n}")]),
+            ("{a: /* } */ 1}", vec![(0, "{a: /* } */ 1}")]),
+            ("[1, # ]\n 2]", vec![(0, "[1, # ]\n 2]")]),
+            ("{url: http://a/[b]}", vec![(0, "{url: http://a/[b]}")]),
+            ("// [1]", vec![(3, "[1]")]),
+            ("[`}`, `${ {a: 1}.a }`]", vec![(0, "[`}`, `${ {a: 1}.a }`]")]),
         ];
         for (t, res) in cases {
             let input = t.chars().collect::<Vec<_>>();
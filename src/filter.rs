@@ -0,0 +1,234 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use regex::Regex;
+
+use crate::{
+    number::Decimal,
+    options::RenderOptions,
+    pretty_value::*,
+    query::{Query, QueryError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+}
+
+// longest operators go first: "<=" must not be parsed as "<"
+const OPS: [(&str, Op); 7] = [
+    ("==", Op::Eq),
+    ("!=", Op::Ne),
+    ("=~", Op::Match),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+];
+
+#[derive(Debug, Clone)]
+enum Predicate {
+    // the path exists
+    Exists,
+    Cmp(Op, String),
+    Match(Regex),
+}
+
+/// A predicate on the detected data: `PATH`, `PATH == VALUE`, `PATH =~ REGEX`, `PATH > NUMBER`
+///
+/// `PATH` is a [`Query`]. A bare `PATH` checks that the path exists. Supported operators are
+/// `==`, `!=`, `<`, `<=`, `>`, `>=` and `=~` (regex match). Numbers are compared numerically,
+/// everything else is compared as text. `VALUE` may be quoted with `"` or `'`
+///
+/// ```
+/// use kras::{parse_str, Filter, ParseOptions};
+///
+/// let filter: Filter = r#".level == "error""#.parse().unwrap();
+/// assert!(parse_str(r#"got {"level": "error"}"#, &ParseOptions::new()).matches(&filter));
+/// assert!(!parse_str(r#"got {"level": "info"}"#, &ParseOptions::new()).matches(&filter));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Filter {
+    query:     Query,
+    predicate: Predicate,
+}
+
+/// An error returned when a [`Filter`] can not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError(String);
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filter: {}", self.0)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl From<QueryError> for FilterError {
+    fn from(err: QueryError) -> Self {
+        FilterError(err.to_string())
+    }
+}
+
+// position of the first operator outside of a quoted key
+fn find_op(s: &str) -> Option<(usize, &'static str, Op)> {
+    let mut quoted = false;
+    let mut escape = false;
+    for (i, c) in s.char_indices() {
+        if escape {
+            escape = false;
+            continue;
+        }
+        match c {
+            '\\' if quoted => escape = true,
+            '"' => quoted = !quoted,
+            _ if !quoted => {
+                if let Some((op_str, op)) = OPS.iter().find(|(op_str, _)| s[i..].starts_with(op_str)) {
+                    return Some((i, op_str, *op));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unquote(s: &str) -> &str {
+    for q in ['"', '\''] {
+        if s.len() >= 2 && s.starts_with(q) && s.ends_with(q) {
+            return &s[1..s.len() - 1];
+        }
+    }
+    s
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((pos, op_str, op)) = find_op(s)
+        else {
+            return Ok(Filter {
+                query:     s.trim().parse()?,
+                predicate: Predicate::Exists,
+            });
+        };
+        let query = s[..pos].trim().parse()?;
+        let operand = unquote(s[pos + op_str.len()..].trim());
+        let predicate = match op {
+            Op::Match => Predicate::Match(Regex::new(operand).map_err(|e| FilterError(format!("{s}: {e}")))?),
+            _ => Predicate::Cmp(op, operand.to_string()),
+        };
+        Ok(Filter { query, predicate })
+    }
+}
+
+impl KrasValue {
+    // text of a scalar value for comparison; containers are compared by their compact form
+    fn filter_text(&self) -> String {
+        match self {
+            KrasValue::Str(_) => self.str_value().unwrap(),
+            KrasValue::Atom(_) => self.atom_name().unwrap(),
+            KrasValue::Ident(s) | KrasValue::Num(OrdF64(_, s)) => s.to_string(),
+            _ => self.render(&RenderOptions::new().indent(0)),
+        }
+    }
+
+    fn filter_cmp(&self, operand: &str) -> Ordering {
+        let number = match self {
+            KrasValue::Num(OrdF64(_, s)) => Decimal::parse(s),
+            KrasValue::Str(_) => Decimal::parse(&self.filter_text()),
+            _ => None,
+        };
+        match (number, Decimal::parse(operand)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => self.filter_text().as_str().cmp(operand),
+        }
+    }
+
+    fn filter_value(&self, predicate: &Predicate) -> bool {
+        match predicate {
+            Predicate::Exists => true,
+            Predicate::Match(re) => re.is_match(&self.filter_text()),
+            Predicate::Cmp(op, operand) => {
+                let ord = self.filter_cmp(operand);
+                match op {
+                    Op::Eq => ord == Ordering::Equal,
+                    Op::Ne => ord != Ordering::Equal,
+                    Op::Lt => ord == Ordering::Less,
+                    Op::Le => ord != Ordering::Greater,
+                    Op::Gt => ord == Ordering::Greater,
+                    Op::Ge => ord != Ordering::Less,
+                    Op::Match => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// Is there any value selected by the filter path that satisfies the filter predicate
+    pub fn matches(&self, filter: &Filter) -> bool {
+        self.select(&filter.query)
+            .iter()
+            .any(|v| v.filter_value(&filter.predicate))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse::parse_str, ParseOptions};
+
+    #[test]
+    fn test_filter() {
+        let input = r#"x {"user": {"id": 10, "name": "bob"}, "tags": ["a", "b"], "ok": true}"#;
+        let val = parse_str(input, &ParseOptions::new());
+        let tests = [
+            (".user.id", true),
+            (".user.age", false),
+            (".user.id == 10", true),
+            (".user.id == 10.0", true),
+            (".user.id != 10", false),
+            (".user.id > 9", true),
+            (".user.id > 9.99999999999999999999", true),
+            (".user.id >= 10", true),
+            (".user.id < 10", false),
+            (".user.id <= 10", true),
+            (".user.name == bob", true),
+            (r#".user.name == "bob""#, true),
+            (".user.name == 'alice'", false),
+            (".user.name =~ ^b.b$", true),
+            (".tags[*] == b", true),
+            (".tags[*] == c", false),
+            (".ok == true", true),
+            (r#".["user"]["name"]=="bob""#, true),
+        ];
+        for (filter, expected) in tests {
+            let filter = filter.parse::<Filter>().unwrap();
+            assert_eq!(val.matches(&filter), expected, "{filter:?}");
+        }
+        assert!(!parse_str("no data", &ParseOptions::new()).matches(&".".parse().unwrap()));
+        // units are not number suffixes
+        let tests = [
+            ("retention=30d ttl=7d", ".retention == 30", false),
+            ("retention=30d ttl=7d", ".ttl == 7d", true),
+            (r#"{"window": 30d}"#, ".window == 30", false),
+        ];
+        for (input, filter, expected) in tests {
+            let val = parse_str(input, &ParseOptions::new());
+            assert_eq!(val.matches(&filter.parse().unwrap()), expected, "{input} {filter}");
+        }
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert!("a == 1".parse::<Filter>().is_err());
+        assert!(".a =~ (".parse::<Filter>().is_err());
+        assert!(r#".["a==b"] == 1"#.parse::<Filter>().is_ok());
+    }
+}
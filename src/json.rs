@@ -0,0 +1,783 @@
+use std::fmt;
+
+use crate::{number::Decimal, options::RenderOptions, pretty_value::*};
+
+/// A value that could not be represented in JSON exactly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonLoss {
+    /// jq-style path of the value inside the detected structure
+    pub path:   String,
+    pub reason: String,
+}
+
+impl fmt::Display for JsonLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            '\x08' => res.push_str("\\b"),
+            '\x0C' => res.push_str("\\f"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+fn lose(losses: &mut Vec<JsonLoss>, path: &str, reason: String) {
+    losses.push(JsonLoss {
+        path: path.to_string(),
+        reason,
+    })
+}
+
+fn json_str(s: &str) -> KrasValue {
+    KrasValue::Str(("\"".to_string(), "".to_string(), json_escape(s)))
+}
+
+fn pair(key: &str, value: KrasValue) -> KrasValue {
+    KrasValue::Pair((Box::new(json_str(key)), ":".to_string(), Box::new(value), None))
+}
+
+fn json_ident(s: &str) -> KrasValue {
+    KrasValue::Ident(s.to_string())
+}
+
+// go prints pointers as &{Name:foo} and maps as map[a:1], php prints arrays as Array ( [a] => 1 ) and
+// array(1) { ["a"]=> int(1) }; the wrapper has no meaning in json
+fn is_wrapper(id: &KrasValue, args: &KrasValue) -> bool {
+    match (id, args) {
+        (KrasValue::Ident(id), KrasValue::List((o, _, _))) => matches!(
+            (id.as_str(), o.as_str()),
+            ("&", "{") | ("map", "[") | ("Array", "(") | ("array", "{") | ("array", "(")
+        ),
+        _ => false,
+    }
+}
+
+// the standard edn tags only tell how to read the string: #inst "2020-01-01", #uuid "f81d4fae"
+fn is_edn_tag(tag: &str) -> bool {
+    tag == "#inst" || tag == "#uuid"
+}
+
+// xml entities: &lt; &amp; &#60; &#x3c;. Unknown entities are kept
+fn xml_unescape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        res.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = entity.and_then(|(e, _)| match e {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match e.strip_prefix("#x").or_else(|| e.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => e
+                    .strip_prefix('#')
+                    .and_then(|n| n.parse().ok().and_then(char::from_u32)),
+            },
+        });
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                res.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+fn is_json_number(s: &str) -> bool {
+    // -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    let s = s.strip_prefix('-').unwrap_or(s).as_bytes();
+    let digits = |s: &[u8], from: usize| from + s[from..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut i = match s.first() {
+        Some(b'0') => 1,
+        Some(c) if c.is_ascii_digit() => digits(s, 0),
+        _ => return false,
+    };
+    if s.get(i) == Some(&b'.') {
+        let j = digits(s, i + 1);
+        if j == i + 1 {
+            return false;
+        }
+        i = j;
+    }
+    if let Some(b'e' | b'E') = s.get(i) {
+        i += 1;
+        if let Some(b'+' | b'-') = s.get(i) {
+            i += 1;
+        }
+        let j = digits(s, i);
+        if j == i {
+            return false;
+        }
+        i = j;
+    }
+    i == s.len()
+}
+
+// positional and keyword args of a python call
+type PyArgs<'a> = (Vec<&'a KrasValue>, Vec<(String, &'a KrasValue)>);
+
+// datetime.datetime(2024, 1, 2, tzinfo=datetime.timezone.utc). Keyword args are pairs if all args are keywords
+fn py_args(args: &KrasValue) -> Option<PyArgs<'_>> {
+    let items = match args {
+        KrasValue::List((o, items, _)) if o == "(" => items,
+        _ => return None,
+    };
+    let (mut pos, mut kw) = (Vec::new(), Vec::new());
+    let mut key = None;
+    for item in items {
+        match item {
+            KrasValue::Pair((k, _, v, _)) => kw.push((k.render(&RenderOptions::new().indent(0)), v.as_ref())),
+            KrasValue::ListItem((k, Some(d))) if d == "=" => key = Some(k.render(&RenderOptions::new().indent(0))),
+            KrasValue::ListItem((v, _)) => match key.take() {
+                Some(k) => kw.push((k, v.as_ref())),
+                None => pos.push(v.as_ref()),
+            },
+            _ => {}
+        }
+    }
+    Some((pos, kw))
+}
+
+fn py_int(val: &KrasValue) -> Option<i128> {
+    match val {
+        KrasValue::Num(OrdF64(_, r)) => r.parse().ok(),
+        _ => None,
+    }
+}
+
+// datetime.timedelta(days=1, seconds=5) or datetime.timedelta(1, 5) in microseconds
+fn py_timedelta(pos: &[&KrasValue], kw: &[(String, &KrasValue)]) -> Option<i128> {
+    let mut parts = [0; 3];
+    for (i, v) in pos.iter().enumerate() {
+        *parts.get_mut(i)? = py_int(v)?;
+    }
+    for (k, v) in kw {
+        let i = ["days", "seconds", "microseconds"].iter().position(|name| name == k)?;
+        parts[i] = py_int(v)?;
+    }
+    parts[0]
+        .checked_mul(86400)?
+        .checked_add(parts[1])?
+        .checked_mul(1_000_000)?
+        .checked_add(parts[2])
+}
+
+// iso 8601 duration: P1DT5.5S
+fn iso_duration(us: i128) -> String {
+    let sign = if us < 0 { "-" } else { "" };
+    let us = us.unsigned_abs();
+    let (days, secs, frac) = (us / 86_400_000_000, us / 1_000_000 % 86400, us % 1_000_000);
+    let mut res = format!("{sign}P");
+    if days > 0 {
+        res += &format!("{days}D");
+    }
+    if secs > 0 || frac > 0 || days == 0 {
+        let frac = if frac > 0 {
+            format!(".{frac:06}").trim_end_matches('0').to_string()
+        }
+        else {
+            "".to_string()
+        };
+        res += &format!("T{secs}{frac}S");
+    }
+    res
+}
+
+// jq-style path of a child value: .["key"][0]
+fn child_path(path: &str, seg: &str) -> String {
+    if path == "." {
+        format!(".[{seg}]")
+    }
+    else {
+        format!("{path}[{seg}]")
+    }
+}
+
+// join a list of converted values with json delimiters
+fn json_list(open: &str, items: Vec<KrasValue>, close: &str) -> KrasValue {
+    let len = items.len();
+    let items = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let d = if i < len - 1 { Some(",".to_string()) } else { None };
+            match item {
+                KrasValue::Pair((k, _, v, _)) => KrasValue::Pair((k, ":".to_string(), v, d)),
+                item => KrasValue::ListItem((Box::new(item), d)),
+            }
+        })
+        .collect();
+    KrasValue::List((open.to_string(), items, close.to_string()))
+}
+
+impl KrasValue {
+    /// Convert the value to a tree that renders as strictly valid JSON
+    ///
+    /// * identifiers like `None`, `nil`, `undef` become `null`, `True` / `False` become booleans,
+    ///   any other identifier becomes a string
+    /// * dicts become objects, all other lists (arrays, tuples, sets) become arrays
+    /// * constructors with named arguments become `{"Name": {...}}`, constructors with positional
+    ///   arguments become tagged arrays `["Name", ...]`
+    /// * python `datetime`, `Decimal`, `UUID`, `OrderedDict` and similar reprs become iso 8601 strings, exact
+    ///   numbers, strings and objects
+    /// * decoded payloads become the decoded value
+    /// * xml elements become `{"tag": ...}` with attributes as `"@name"` keys and text as a string or `"#text"`
+    ///
+    /// Every value that could not be represented exactly is reported to `losses`.
+    /// The raw text of a [`KrasValue::RawList`] is kept as is
+    pub fn to_json(&self, losses: &mut Vec<JsonLoss>) -> KrasValue {
+        match self {
+            KrasValue::RawList(items) => KrasValue::RawList(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        KrasValue::RawStr(_) => item.clone(),
+                        _ => item.json_value(".", losses),
+                    })
+                    .collect(),
+            ),
+            KrasValue::RawStr(_) => self.clone(),
+            _ => self.json_value(".", losses),
+        }
+    }
+
+    fn json_value(&self, path: &str, losses: &mut Vec<JsonLoss>) -> KrasValue {
+        match self {
+            KrasValue::Str((_, p, _)) => {
+                // raw and unicode strings are exact after decoding
+                if !p.chars().all(|c| "rRuU".contains(c)) {
+                    lose(losses, path, format!("string prefix '{p}' dropped"));
+                }
+                json_str(&self.str_value().unwrap())
+            }
+            KrasValue::Ident(s) => match s.as_str() {
+                "null" | "None" | "nil" | "<nil>" | "undef" | "NULL" | "Null" | "undefined" => json_ident("null"),
+                "true" | "True" | "TRUE" => json_ident("true"),
+                "false" | "False" | "FALSE" => json_ident("false"),
+                _ => json_str(s),
+            },
+            KrasValue::Atom(_) => json_str(&self.atom_name().unwrap()),
+            KrasValue::Num(OrdF64(n, r)) => {
+                if is_json_number(r) {
+                    KrasValue::Num(OrdF64(*n, r.to_string()))
+                }
+                else if let Some(d) = Decimal::parse(r) {
+                    KrasValue::Num(OrdF64(*n, d.to_plain_string()))
+                }
+                else if n.is_finite() {
+                    KrasValue::Num(OrdF64(*n, n.to_string()))
+                }
+                else {
+                    lose(losses, path, format!("number {r} is not representable"));
+                    json_ident("null")
+                }
+            }
+            KrasValue::ListItem((v, _)) => v.json_value(path, losses),
+            KrasValue::Pair((k, _, v, _)) => {
+                let key = k.json_key(path, losses);
+                let path = child_path(path, &json_str(&key).render(&RenderOptions::new()));
+                KrasValue::Pair((
+                    Box::new(json_str(&key)),
+                    ":".to_string(),
+                    Box::new(v.json_value(&path, losses)),
+                    None,
+                ))
+            }
+            KrasValue::List((_, items, _)) => match self.binary_str() {
+                Some(s) => json_str(&s),
+                None => {
+                    if items.last().is_some_and(|item| item.is_truncated()) {
+                        lose(losses, path, "data is truncated".to_string());
+                    }
+                    let items = items
+                        .iter()
+                        .filter(|item| !item.is_truncated())
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if self.is_dict() {
+                        json_list(
+                            "{",
+                            items
+                                .iter()
+                                .filter(|item| !item.is_comment())
+                                .map(|item| item.json_value(path, losses))
+                                .collect(),
+                            "}",
+                        )
+                    }
+                    else {
+                        // comments have no json representation and are dropped
+                        let items = items
+                            .iter()
+                            .filter(|item| !item.is_comment())
+                            .enumerate()
+                            .map(|(i, item)| item.json_value(&child_path(path, &i.to_string()), losses))
+                            .collect();
+                        json_list("[", items, "]")
+                    }
+                }
+            },
+            KrasValue::Constructor((id, args)) if is_wrapper(id, args) => args.json_value(path, losses),
+            KrasValue::Constructor((id, args)) if matches!(id.as_ref(), KrasValue::Ident(t) if is_edn_tag(t)) => {
+                args.json_value(path, losses)
+            }
+            KrasValue::Constructor((id, args)) => {
+                // python values are tried first, their losses only count if they convert
+                let mut python_losses = Vec::new();
+                if let Some(res) = self.python_json(path, &mut python_losses) {
+                    losses.extend(python_losses);
+                    return res;
+                }
+                let name = id.json_key(path, losses);
+                if args.is_dict() {
+                    json_list(
+                        "{",
+                        vec![KrasValue::Pair((
+                            Box::new(json_str(&name)),
+                            ":".to_string(),
+                            Box::new(
+                                args.json_value(&child_path(path, &format!("\"{}\"", json_escape(&name))), losses),
+                            ),
+                            None,
+                        ))],
+                        "}",
+                    )
+                }
+                else if let KrasValue::List((_, items, _)) = args.as_ref() {
+                    let mut res = vec![json_str(&name)];
+                    res.extend(
+                        items
+                            .iter()
+                            .filter(|item| !item.is_comment())
+                            .enumerate()
+                            .map(|(i, item)| item.json_value(&child_path(path, &(i + 1).to_string()), losses)),
+                    );
+                    json_list("[", res, "]")
+                }
+                else {
+                    json_list("[", vec![json_str(&name), args.json_value(path, losses)], "]")
+                }
+            }
+            KrasValue::Element((name, _, _, _)) => {
+                let path = child_path(path, &json_str(name).render(&RenderOptions::new()));
+                json_list("{", vec![pair(name, self.xml_content(&path, losses))], "}")
+            }
+            KrasValue::Text(_) => json_str(&self.xml_text().unwrap()),
+            KrasValue::Decoded((_, v)) => v.json_value(path, losses),
+            KrasValue::RawStr(s) | KrasValue::Comment(s) => json_str(s),
+            KrasValue::Truncated => {
+                lose(losses, path, "data is truncated".to_string());
+                json_ident("null")
+            }
+            KrasValue::RawList(_) => {
+                lose(losses, path, "text with nested data converted to a string".to_string());
+                json_str(&self.render(&RenderOptions::new().indent(0)))
+            }
+        }
+    }
+
+    // json keys can only be strings
+    fn json_key(&self, path: &str, losses: &mut Vec<JsonLoss>) -> String {
+        match self {
+            KrasValue::Str(_) => self.str_value().unwrap(),
+            KrasValue::Ident(s) => s.to_string(),
+            KrasValue::Atom(_) => self.atom_name().unwrap(),
+            KrasValue::Num(OrdF64(_, r)) => r.to_string(),
+            _ => {
+                if let Some(key) = self.binary_str() {
+                    key
+                }
+                else if let Some(key) = self.bracketed_key() {
+                    key.json_key(path, losses)
+                }
+                else {
+                    let key = self.render(&RenderOptions::new().indent(0));
+                    lose(losses, path, format!("key {key} converted to a string"));
+                    key
+                }
+            }
+        }
+    }
+
+    // erlang binary of a single string: <<"abc">>
+    fn binary_str(&self) -> Option<String> {
+        match self {
+            KrasValue::List((o, items, _)) if o == "<<" && items.len() == 1 => match &items[0] {
+                KrasValue::ListItem((v, None)) => v.str_value(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // the text of an xml text node: cdata as is, entities decoded in the rest
+    fn xml_text(&self) -> Option<String> {
+        match self {
+            KrasValue::Text(s) => {
+                let s = s.trim();
+                let cdata = s.strip_prefix("<![CDATA[").and_then(|s| s.strip_suffix("]]>"));
+                Some(cdata.map_or_else(|| xml_unescape(s), |s| s.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    // the content of an xml element, like xmltodict does: attributes become "@name" keys, child elements
+    // become keys (an array if the element is repeated), the text becomes "#text". An element with only
+    // text becomes a string, an empty element becomes null
+    fn xml_content(&self, path: &str, losses: &mut Vec<JsonLoss>) -> KrasValue {
+        let (attrs, children) = match self {
+            KrasValue::Element((_, attrs, _, children)) => (attrs, children.iter().flatten().collect::<Vec<_>>()),
+            _ => return self.json_value(path, losses),
+        };
+        let text = children.iter().filter_map(|x| x.xml_text()).collect::<Vec<_>>();
+        let text = text.join(" ");
+        let elements = children.iter().filter_map(|x| match x {
+            KrasValue::Element((name, _, _, _)) => Some((name, x)),
+            _ => None,
+        });
+        let elements = elements.collect::<Vec<_>>();
+        if attrs.is_empty() && elements.is_empty() {
+            return match text.is_empty() {
+                true => json_ident("null"),
+                false => json_str(&text),
+            };
+        }
+        let mut keys: Vec<(String, Vec<KrasValue>)> = Vec::new();
+        for attr in attrs {
+            if let KrasValue::Pair((k, _, v, _)) = attr {
+                if let (KrasValue::Ident(k), KrasValue::Str((_, _, v))) = (k.as_ref(), v.as_ref()) {
+                    keys.push((format!("@{k}"), vec![json_str(&xml_unescape(v))]));
+                }
+            }
+        }
+        for (name, x) in elements {
+            let child = child_path(path, &json_str(name).render(&RenderOptions::new()));
+            let value = x.xml_content(&child, losses);
+            match keys.iter().position(|(k, _)| k == name) {
+                Some(i) => {
+                    if i + 1 != keys.len() {
+                        lose(losses, path, format!("order of <{name}> elements is lost"));
+                    }
+                    keys[i].1.push(value);
+                }
+                None => keys.push((name.to_string(), vec![value])),
+            }
+        }
+        if !text.is_empty() {
+            keys.push(("#text".to_string(), vec![json_str(&text)]));
+        }
+        let pairs = keys
+            .into_iter()
+            .map(|(k, mut v)| match v.len() {
+                1 => pair(&k, v.pop().unwrap()),
+                _ => pair(&k, json_list("[", v, "]")),
+            })
+            .collect();
+        json_list("{", pairs, "}")
+    }
+
+    // python reprs of stdlib types: datetime.datetime(2024, 1, 2, 3, 4), Decimal('1.20'), UUID('...'),
+    // OrderedDict([('a', 1)]), defaultdict(<class 'list'>, {...}). Dates become iso 8601 strings, decimals
+    // become exact numbers, dicts become objects. None if the value is not one of them
+    fn python_json(&self, path: &str, losses: &mut Vec<JsonLoss>) -> Option<KrasValue> {
+        let (name, (pos, kw)) = match self {
+            KrasValue::Constructor((id, args)) => match id.as_ref() {
+                KrasValue::Ident(name) => (name.as_str(), py_args(args)?),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let ints = || pos.iter().map(|v| py_int(v)).collect::<Option<Vec<_>>>();
+        let get = |n: &[i128], i: usize| n.get(i).copied().unwrap_or(0);
+        match name {
+            "datetime.date" => {
+                let n = ints().filter(|n| n.len() == 3)?;
+                Some(json_str(&format!("{:04}-{:02}-{:02}", n[0], n[1], n[2])))
+            }
+            "datetime.datetime" | "datetime.time" => {
+                let n = ints()?;
+                // time has no date part
+                let (date, n) = match name {
+                    "datetime.datetime" if (3..=7).contains(&n.len()) => {
+                        (format!("{:04}-{:02}-{:02}T", n[0], n[1], n[2]), &n[3..])
+                    }
+                    "datetime.time" if n.len() <= 4 => ("".to_string(), &n[..]),
+                    _ => return None,
+                };
+                let mut res = format!("{date}{:02}:{:02}:{:02}", get(n, 0), get(n, 1), get(n, 2));
+                if get(n, 3) != 0 {
+                    res += &format!(".{:06}", get(n, 3));
+                }
+                if let Some((_, tz)) = kw.iter().find(|(k, _)| k == "tzinfo") {
+                    res += &tz.python_tz(path, losses);
+                }
+                Some(json_str(&res))
+            }
+            "datetime.timedelta" => Some(json_str(&iso_duration(py_timedelta(&pos, &kw)?))),
+            "Decimal" => {
+                let s = pos.first().filter(|_| pos.len() == 1)?.str_value()?;
+                let num = KrasValue::Num(OrdF64(s.parse().unwrap_or(f64::NAN), s));
+                Some(num.json_value(path, losses))
+            }
+            "UUID" => Some(json_str(&pos.first().filter(|_| pos.len() == 1)?.str_value()?)),
+            // deque([1, 2], maxlen=10)
+            "deque" => match &pos[..] {
+                [items @ KrasValue::List((o, _, _))] if o == "[" => Some(items.json_value(path, losses)),
+                _ => None,
+            },
+            "OrderedDict" | "Counter" | "defaultdict" => {
+                // the default factory of defaultdict has no meaning in json
+                let items = match (name, &pos[..]) {
+                    (_, []) => return Some(json_list("{", vec![], "}")),
+                    ("defaultdict", [_, items]) | ("OrderedDict" | "Counter", [items]) => items,
+                    _ => return None,
+                };
+                match items {
+                    // OrderedDict([('a', 1), ('b', 2)])
+                    KrasValue::List((o, items, _)) if o == "[" => {
+                        let pairs = items
+                            .iter()
+                            .filter(|item| !item.is_comment())
+                            .map(|item| match item {
+                                KrasValue::ListItem((v, _)) => match v.as_ref() {
+                                    KrasValue::List((o, kv, _)) if o == "(" => match &kv[..] {
+                                        [KrasValue::ListItem((k, _)), KrasValue::ListItem((v, _))] => Some((k, v)),
+                                        _ => None,
+                                    },
+                                    _ => None,
+                                },
+                                _ => None,
+                            })
+                            .collect::<Option<Vec<_>>>()?;
+                        let pairs = pairs
+                            .into_iter()
+                            .map(|(k, v)| {
+                                let key = k.json_key(path, losses);
+                                let child = child_path(path, &json_str(&key).render(&RenderOptions::new()));
+                                pair(&key, v.json_value(&child, losses))
+                            })
+                            .collect();
+                        Some(json_list("{", pairs, "}"))
+                    }
+                    items if items.is_dict() => Some(items.json_value(path, losses)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // the utc offset of a python tzinfo: datetime.timezone.utc, <UTC>, tzutc(),
+    // datetime.timezone(datetime.timedelta(seconds=3600))
+    fn python_tz(&self, path: &str, losses: &mut Vec<JsonLoss>) -> String {
+        let text = self.render(&RenderOptions::new().indent(0));
+        let offset = match self {
+            KrasValue::Constructor((id, args)) if id.as_ref() == &KrasValue::Ident("datetime.timezone".to_string()) => {
+                let delta = py_args(args).and_then(|(pos, _)| match pos[..] {
+                    [KrasValue::Constructor((_, args))] => py_args(args),
+                    _ => None,
+                });
+                delta.and_then(|(pos, kw)| py_timedelta(&pos, &kw))
+            }
+            _ if ["datetime.timezone.utc", "<UTC>", "tzutc()", "UTC"].contains(&text.as_str()) => Some(0),
+            _ => None,
+        };
+        match offset {
+            Some(0) => "+00:00".to_string(),
+            Some(us) => {
+                let sign = if us < 0 { '-' } else { '+' };
+                let minutes = us.unsigned_abs() / 60_000_000;
+                format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+            }
+            None => {
+                lose(losses, path, format!("timezone {text} dropped"));
+                "".to_string()
+            }
+        }
+    }
+
+    fn is_dict(&self) -> bool {
+        match self {
+            // {}, erlang #{}, elixir %{}
+            KrasValue::List((o, items, _)) if items.iter().all(|item| item.is_comment() || item.is_truncated()) => {
+                o.ends_with('{')
+            }
+            KrasValue::List((_, items, _)) => items
+                .iter()
+                .all(|item| matches!(item, KrasValue::Pair(_) | KrasValue::Comment(_)) || item.is_truncated()),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse::parse_str, ParseOptions};
+
+    fn to_json(s: &str) -> (String, Vec<JsonLoss>) {
+        let mut losses = Vec::new();
+        let res = parse_str(s, &ParseOptions::new()).to_json(&mut losses);
+        (res.render(&RenderOptions::new().indent(0)), losses)
+    }
+
+    #[test]
+    fn test_json() {
+        let tests = [
+            (r#"{"a": 1, "b": [1, 2.5]}"#, r#"{"a": 1, "b": [1, 2.5]}"#),
+            (r#"{"a":"b","c":{"d":"e"}}"#, r#"{"a": "b", "c": {"d": "e"}}"#),
+            (
+                "{'a': None, 'b': True, 'c': (1, 2)}",
+                r#"{"a": null, "b": true, "c": [1, 2]}"#,
+            ),
+            ("{a => 'x', b => undef}", r#"{"a": "x", "b": null}"#),
+            ("[0x1f, 1e3]", "[31, 1e3]"),
+            ("[0xffffffffffffffffff]", "[4722366482869645213695]"),
+            (r#"['a"b']"#, r#"["a\"b"]"#),
+            ("[Foo { a: 1, b: nil }]", r#"[{"Foo": {"a": 1, "b": null}}]"#),
+            (
+                "got Foo { a: 1, b: Bar { c: 2 } } ok",
+                r#"got {"Foo": {"a": 1, "b": {"Bar": {"c": 2}}}} ok"#,
+            ),
+            ("Foo {\n    a: 1,\n    b: \"x\",\n}", r#"{"Foo": {"a": 1, "b": "x"}}"#),
+            (r#"Got {"a": 1}"#, r#"Got {"a": 1}"#),
+            ("[Some(1, 2)]", r#"[["Some", 1, 2]]"#),
+            ("line {1: 2} end", r#"line {"1": 2} end"#),
+            ("{}", "{}"),
+            ("[]", "[]"),
+            ("(foo bar)", r#"["foo", "bar"]"#),
+            (
+                "&{Name:foo Tags:[a b] M:map[x:1] P:<nil>}",
+                r#"{"Name": "foo", "Tags": ["a", "b"], "M": {"x": 1}, "P": null}"#,
+            ),
+            (
+                r#"Array ( [a] => x y [b] => Array ( [0] => 1 ) ) array(1) { ["c"]=> NULL }"#,
+                r#"{"a": "x y", "b": {"0": 1}} {"c": null}"#,
+            ),
+            (
+                r#"#{<<"id">> => 1, tags => [:a, 'b'], m => %{}}"#,
+                r#"{"id": 1, "tags": ["a", "b"], "m": {}}"#,
+            ),
+            (
+                r#"{:id 1, :tags #{:a :b} :at #inst "2020-01-01T00:00:00Z" :v [nil #point [1 2]]}"#,
+                r##"{"id": 1, "tags": ["a", "b"], "at": "2020-01-01T00:00:00Z", "v": [null, ["#point", 1, 2]]}"##,
+            ),
+            (
+                r#"{ ["key"] = 1, [2] = "b", x = { y = true } }"#,
+                r#"{"key": 1, "2": "b", "x": {"y": true}}"#,
+            ),
+            (
+                r#"#<User id: 1, name: "x", roles: [:admin]>"#,
+                r#"{"User": {"id": 1, "name": "x", "roles": ["admin"]}}"#,
+            ),
+            (
+                "User(id=1, name=Bob Smith, tags=[a, b], email=null)",
+                r#"{"User": {"id": 1, "name": "Bob Smith", "tags": ["a", "b"], "email": null}}"#,
+            ),
+            (
+                r#"<user id="1"><name>Bob &amp; Co</name><tag>a</tag><tag>b</tag><br/>x</user>"#,
+                r##"{"user": {"@id": "1", "name": "Bob & Co", "tag": ["a", "b"], "br": null, "#text": "x"}}"##,
+            ),
+            (
+                "{'at': datetime.datetime(2024, 1, 2, 3, 4, 5, 120000, tzinfo=datetime.timezone.utc), \
+                 'd': datetime.date(2024, 1, 2), 'p': Decimal('1.20'), 'id': UUID('1234-5678')}",
+                r#"{"at": "2024-01-02T03:04:05.120000+00:00", "d": "2024-01-02", "p": 1.20, "id": "1234-5678"}"#,
+            ),
+            (
+                "OrderedDict([('a', 1), ('b', 2)]) defaultdict(<class 'list'>, {'a': [1]}) \
+                 datetime.timedelta(days=1, seconds=5, microseconds=500000)",
+                r#"{"a": 1, "b": 2} {"a": [1]} "P1DT5.5S""#,
+            ),
+            (
+                "[1E+10000000000L, +1e30000000000, .5e-100]",
+                "[1e10000000000, 1e30000000000, 5e-101]",
+            ),
+            (
+                "datetime.timedelta(days=999999999, seconds=86399, microseconds=999999)",
+                r#""P999999999DT86399.999999S""#,
+            ),
+            (
+                r#"at 12:00 level=info user="bob" took=12ms n=5 done"#,
+                r#"at 12:00 {"level": "info", "user": "bob", "took": "12ms", "n": 5} done"#,
+            ),
+        ];
+        for (input, expected) in tests {
+            let (res, losses) = to_json(input);
+            assert_eq!(res, expected);
+            assert_eq!(losses, vec![]);
+        }
+    }
+
+    #[test]
+    fn test_json_deep() {
+        // every python value is converted once, not once per check
+        let input = "OrderedDict([('a', ".repeat(22) + "1" + &")])".repeat(22);
+        let (res, losses) = to_json(&input);
+        assert_eq!(res, r#"{"a": "#.repeat(22) + "1" + &"}".repeat(22));
+        assert_eq!(losses, vec![]);
+    }
+
+    #[test]
+    fn test_json_truncated() {
+        let mut losses = Vec::new();
+        let res = parse_str(r#"{"a": [1, 2], "b": {"c": "#, &ParseOptions::new().recover(true)).to_json(&mut losses);
+        assert_eq!(
+            res.render(&RenderOptions::new().indent(0)),
+            r#"{"a": [1, 2], "b": {"c": null}}"#
+        );
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].path, r#".["b"]["c"]"#);
+        let res = parse_str("[1, 2", &ParseOptions::new().recover(true)).to_json(&mut losses);
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), "[1, 2]");
+        assert_eq!(losses.len(), 2);
+    }
+
+    #[test]
+    fn test_json_loss() {
+        let (res, losses) = to_json("{(1, 2): b'x'}");
+        assert_eq!(res, r#"{"(1, 2)": "x"}"#);
+        assert_eq!(
+            losses,
+            vec![
+                JsonLoss {
+                    path:   ".".to_string(),
+                    reason: "key (1, 2) converted to a string".to_string(),
+                },
+                JsonLoss {
+                    path:   r#".["(1, 2)"]"#.to_string(),
+                    reason: "string prefix 'b' dropped".to_string(),
+                },
+            ]
+        );
+        let (res, losses) = to_json("datetime.datetime(2024, 1, 2, tzinfo=zoneinfo.ZoneInfo(key='Europe/Berlin'))");
+        assert_eq!(res, r#""2024-01-02T00:00:00""#);
+        assert_eq!(
+            losses[0].reason,
+            "timezone zoneinfo.ZoneInfo(key='Europe/Berlin') dropped"
+        );
+    }
+}
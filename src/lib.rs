@@ -0,0 +1,44 @@
+//! kras - detect, highlight and pretty print structured data inside plain text
+//!
+//! The library exposes the same machinery the `kras` binary uses:
+//!
+//! * [`parse_str`] scans a string, finds everything that looks like structured data and
+//!   returns a [`KrasValue::RawList`] of parsed values interleaved with the raw text around them
+//! * [`KrasValue::render`] pretty-prints (and optionally colorizes) a parsed value
+//! * [`DetectDataIter`] and [`DetectDataV2`] are the detectors that find candidate data chunks
+//!
+//! ```
+//! use kras::{parse_str, ParseOptions, RenderOptions};
+//!
+//! let value = parse_str(r#"got {"a": 1, "b": [1, 2]} from server"#, &ParseOptions::new());
+//! let out = value.render(&RenderOptions::new());
+//! assert_eq!(out, r#"got {"a": 1, "b": [1, 2]} from server"#);
+//! ```
+
+#![allow(clippy::redundant_field_names)]
+
+#[macro_use]
+extern crate log;
+
+mod decode;
+mod detect;
+mod detect2;
+mod filter;
+mod json;
+mod number;
+mod options;
+mod parse;
+mod pretty_value;
+mod query;
+mod stopwatch;
+mod yaml;
+
+pub use detect::DetectDataIter;
+pub use detect2::DetectDataV2;
+pub use filter::{Filter, FilterError};
+pub use json::JsonLoss;
+pub use options::{Detector, OutputFormat, ParseOptions, RenderOptions};
+pub use parse::parse_str;
+pub use pretty_value::{KrasValue, KrasVisitor, OrdF64};
+pub use query::{Query, QueryError};
+pub use termcolor::ColorChoice;
@@ -25,26 +25,13 @@ use fileinput::FileInput;
 
 extern crate crossbeam;
 
-extern crate pom;
-
-extern crate pretty;
-
 extern crate num_cpus;
 
 extern crate signal_hook;
 
 use crossbeam::channel::bounded;
-use pretty::termcolor::ColorChoice;
-
-mod detect;
-mod detect2;
-
-mod pretty_value;
-
-mod parse;
-use parse::parse_str;
 
-mod stopwatch;
+use kras::{parse_str, ColorChoice, Detector, Filter, OutputFormat, ParseOptions, Query, RenderOptions};
 
 mod printer;
 use printer::Printer;
@@ -57,6 +44,13 @@ enum ColorChoiceArg {
     No,
 }
 
+#[derive(Clone, Debug, ValueEnum)]
+enum OutputFormatArg {
+    Pretty,
+    Json,
+    Yaml,
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -85,6 +79,50 @@ struct Cli {
     )]
     force_color: bool,
 
+    #[arg(
+        value_enum,
+        short='o',
+        long,
+        help="output format. json and yaml convert all detected data to valid json or yaml",
+        default_value_t = OutputFormatArg::Pretty,
+    )]
+    output: OutputFormatArg,
+
+    #[arg(
+        short = 'u',
+        long,
+        help = "show strings with escaped newlines (stack traces, sql, nested json) as indented multi-line text",
+        default_value_t = false
+    )]
+    unescape: bool,
+
+    #[arg(
+        long,
+        help = "print only the detected data, each value on its own line",
+        default_value_t = false
+    )]
+    only_data: bool,
+
+    #[arg(
+        long,
+        help = "print detected data as newline delimited json. Alias for --only-data --output json --indent 0",
+        default_value_t = false
+    )]
+    ndjson: bool,
+
+    #[arg(
+        short = 'q',
+        long,
+        help = "print only the values matching a jq-like path: .a.b[0], ..id, .a[*]"
+    )]
+    query: Option<Query>,
+
+    #[arg(
+        long = "where",
+        help = "print only lines with data matching a predicate: '.a.b', '.a == 1', '.a =~ regex', '.a > 1'. Can be repeated"
+    )]
+    filter: Vec<Filter>,
+
     #[arg(
         short='s',
         long,
@@ -125,6 +163,20 @@ struct Cli {
     )]
     multiline: bool,
 
+    #[arg(
+        long,
+        help = "try to parse data that is cut at the end of line. The cut is marked with …",
+        default_value_t = false
+    )]
+    recover: bool,
+
+    #[arg(
+        long,
+        help = "decode url-encoded, hex and base64 payloads and parse them. Implies --recursive",
+        default_value_t = false
+    )]
+    decode: bool,
+
     #[arg(
         long,
         help="use more robust, but slower method to detect structured data",
@@ -146,6 +198,49 @@ struct Cli {
     input: Vec<String>,
 }
 
+impl Cli {
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions::new()
+            .sort(self.sort)
+            .recursive(self.recursive)
+            .recover(self.recover)
+            .decode(self.decode)
+            .detector(if self.robust { Detector::Robust } else { Detector::Fast })
+    }
+
+    fn render_options(&self) -> RenderOptions {
+        let color_choice = if self.force_color {
+            ColorChoice::Always
+        }
+        else {
+            match self.color {
+                ColorChoiceArg::Yes => ColorChoice::Always,
+                ColorChoiceArg::No => ColorChoice::Never,
+                ColorChoiceArg::Auto => {
+                    if atty::is(atty::Stream::Stdout) {
+                        ColorChoice::Auto
+                    }
+                    else {
+                        ColorChoice::Never
+                    }
+                }
+            }
+        };
+        let format = match self.output {
+            _ if self.ndjson => OutputFormat::Json,
+            OutputFormatArg::Pretty => OutputFormat::Pretty,
+            OutputFormatArg::Json => OutputFormat::Json,
+            OutputFormatArg::Yaml => OutputFormat::Yaml,
+        };
+        RenderOptions::new()
+            .indent(if self.ndjson { 0 } else { self.indent })
+            .width(self.width)
+            .color(color_choice)
+            .format(format)
+            .unescape(self.unescape)
+    }
+}
+
 fn main() {
     let signal_flag = Arc::new(AtomicBool::new(false));
     signal_hook::flag::register(SIGPIPE, Arc::clone(&signal_flag)).unwrap();
@@ -154,30 +249,12 @@ fn main() {
 
     init_logger(if args.debug {2} else {0});
 
-    let min_len = if args.indent == 0 {
-        std::usize::MAX
-    }
-    else {
-        args.width
-    };
+    let parse_options = Arc::new(args.parse_options());
+    let render_options = Arc::new(args.render_options());
 
-    let color_choice = if args.force_color {
-        ColorChoice::Always
-    }
-    else {
-        match args.color {
-            ColorChoiceArg::Yes => ColorChoice::Always,
-            ColorChoiceArg::No => ColorChoice::Never,
-            ColorChoiceArg::Auto => {
-                if atty::is(atty::Stream::Stdout) {
-                    ColorChoice::Auto
-                }
-                else {
-                    ColorChoice::Never
-                }
-            }
-        }
-    };
+    let only_data = args.only_data || args.ndjson || args.query.is_some();
+    let query = Arc::new(args.query.clone());
+    let filters = Arc::new(args.filter.clone());
 
     let jobs = if args.multiline {
         1
@@ -201,13 +278,39 @@ fn main() {
     (0..jobs).for_each(|_| {
         let input_receiver = input_receiver.clone();
         let output_sender = output_sender.clone();
+        let parse_options = Arc::clone(&parse_options);
+        let render_options = Arc::clone(&render_options);
+        let query = Arc::clone(&query);
+        let filters = Arc::clone(&filters);
         thread::spawn(move || {
             let signal_flag = Arc::new(AtomicBool::new(false));
             signal_hook::flag::register(SIGPIPE, Arc::clone(&signal_flag)).unwrap();
             while let Ok((i, s)) = input_receiver.recv() {
-                let line = parse_str(&s, args.sort, args.recursive, args.robust);
+                let line = parse_str(&s, &parse_options);
                 debug!("line = {:?}", line);
-                let rendered_str = line.render(args.indent, min_len, color_choice);
+                let rendered_str = if !filters.iter().all(|f| line.matches(f)) {
+                    None
+                }
+                else if only_data {
+                    let data = match query.as_ref() {
+                        Some(query) => line.select(query),
+                        None => line.data(),
+                    };
+                    if data.is_empty() {
+                        None
+                    }
+                    else {
+                        Some(
+                            data.iter()
+                                .map(|v| v.render(&render_options))
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                        )
+                    }
+                }
+                else {
+                    Some(line.render(&render_options))
+                };
                 if let Err(err) = output_sender.send((i, rendered_str)) {
                     // likely a pipe is closed on us
                     debug!("send error: {}", err);
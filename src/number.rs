@@ -0,0 +1,239 @@
+use std::cmp::Ordering;
+
+/*
+Exact representation of a number literal, used to compare numbers that do not fit into f64:
+64-bit ids, pointers, long decimals
+
+value = (-1)^neg * 0.d1d2d3... * 10^exp
+
+digits have no leading and no trailing zeros, so two equal numbers always have the same
+representation and numbers with the same sign and exponent can be compared digit by digit.
+Zero has no digits
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    neg:    bool,
+    digits: Vec<u8>,
+    exp:    i64,
+}
+
+/// type suffixes of number literals: rust 5u32, 1.0f64, java 10L. Longer suffixes go first.
+/// Other one letter suffixes are units more often than types: retention=30d
+pub(crate) const SUFFIXES: [&str; 15] = [
+    "u128", "i128", "usize", "isize", "u16", "u32", "u64", "i16", "i32", "i64", "f32", "f64", "u8", "i8", "L",
+];
+
+fn is_digits(s: &str, radix: u32) -> bool {
+    s.chars().all(|c| c.is_digit(radix))
+}
+
+impl Decimal {
+    // None if the exponent of the normalized number doesn't fit into i64
+    fn new(neg: bool, mut digits: Vec<u8>, mut exp: i64) -> Option<Self> {
+        let leading = digits.iter().take_while(|d| **d == 0).count();
+        digits.drain(..leading);
+        exp = exp.checked_sub(leading as i64)?;
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        if digits.is_empty() {
+            return Some(Decimal {
+                neg: false,
+                digits,
+                exp: 0,
+            });
+        }
+        Some(Decimal { neg, digits, exp })
+    }
+
+    fn from_radix(neg: bool, s: &str, radix: u32) -> Option<Self> {
+        // big-endian decimal digits of the number
+        let mut res: Vec<u8> = Vec::new();
+        for c in s.chars() {
+            let mut carry = c.to_digit(radix).unwrap();
+            for d in res.iter_mut().rev() {
+                let x = *d as u32 * radix + carry;
+                *d = (x % 10) as u8;
+                carry = x / 10;
+            }
+            while carry > 0 {
+                res.insert(0, (carry % 10) as u8);
+                carry /= 10;
+            }
+        }
+        let exp = res.len() as i64;
+        Decimal::new(neg, res, exp)
+    }
+
+    /// parse a number literal: 123, -1.5e10, 0xff, 0b1010, 0o755, 1_000, 5u32, .5. Returns None for anything else
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let s = s.replace('_', "");
+        let (neg, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(&s)),
+        };
+        for (prefix, radix) in [("0x", 16), ("0X", 16), ("0o", 8), ("0O", 8), ("0b", 2), ("0B", 2)] {
+            if let Some(digits) = s.strip_prefix(prefix) {
+                if digits.is_empty() || !is_digits(digits, radix) {
+                    return None;
+                }
+                return Decimal::from_radix(neg, digits, radix);
+            }
+        }
+        let s = SUFFIXES
+            .iter()
+            .find_map(|suffix| {
+                s.strip_suffix(suffix)
+                    .filter(|s| s.ends_with(|c: char| c.is_ascii_digit()))
+            })
+            .unwrap_or(s);
+        let (mantissa, exp) = match s.find(['e', 'E']) {
+            Some(pos) => (&s[..pos], s[pos + 1..].parse::<i64>().ok()?),
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if (int.is_empty() && frac.is_empty()) || !is_digits(int, 10) || !is_digits(frac, 10) {
+            return None;
+        }
+        let digits = int.chars().chain(frac.chars()).map(|c| c as u8 - b'0').collect();
+        // 1e9223372036854775807 is not representable
+        let exp = (int.len() as i64).checked_add(exp)?;
+        Decimal::new(neg, digits, exp)
+    }
+
+    /// plain decimal notation of the number: 0.0015, 1000. A number that would be padded with many zeros is
+    /// written with an exponent instead: 1e+100 is 1e100, not a hundred zeros
+    pub(crate) fn to_plain_string(&self) -> String {
+        const MAX_ZEROS: i64 = 20;
+        if self.digits.is_empty() {
+            return "0".to_string();
+        }
+        let digits = self.digits.iter().map(|d| (b'0' + d) as char).collect::<String>();
+        let len = digits.len() as i64;
+        let res = if self.exp < -MAX_ZEROS || self.exp > len.saturating_add(MAX_ZEROS) {
+            let frac = if len > 1 {
+                format!(".{}", &digits[1..])
+            }
+            else {
+                "".to_string()
+            };
+            format!("{}{frac}e{}", &digits[..1], self.exp as i128 - 1)
+        }
+        else if self.exp <= 0 {
+            format!("0.{}{digits}", "0".repeat(self.exp.unsigned_abs() as usize))
+        }
+        else if self.exp >= len {
+            format!("{digits}{}", "0".repeat((self.exp - len) as usize))
+        }
+        else {
+            format!("{}.{}", &digits[..self.exp as usize], &digits[self.exp as usize..])
+        };
+        if self.neg {
+            format!("-{res}")
+        }
+        else {
+            res
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = |d: &Decimal| match (d.neg, d.digits.is_empty()) {
+            (_, true) => 0,
+            (true, _) => -1,
+            (false, _) => 1,
+        };
+        let ord = sign(self).cmp(&sign(other));
+        if ord != Ordering::Equal || sign(self) == 0 {
+            return ord;
+        }
+        let ord = self.exp.cmp(&other.exp).then_with(|| self.digits.cmp(&other.digits));
+        if self.neg {
+            ord.reverse()
+        }
+        else {
+            ord
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decimal_cmp() {
+        let tests = [
+            ("1", "2", Ordering::Less),
+            ("10", "9", Ordering::Greater),
+            ("1.0", "1", Ordering::Equal),
+            ("0.10", "1e-1", Ordering::Equal),
+            ("-0", "0", Ordering::Equal),
+            ("-1", "0", Ordering::Less),
+            ("-10", "-9", Ordering::Less),
+            ("0.123", "0.12", Ordering::Greater),
+            ("9007199254740993", "9007199254740992", Ordering::Greater),
+            ("18446744073709551615", "18446744073709551614", Ordering::Greater),
+            ("0x10", "16", Ordering::Equal),
+            ("0xffffffffffffffff", "0xfffffffffffffffe", Ordering::Greater),
+            ("0xffffffffffffffff", "18446744073709551615", Ordering::Equal),
+            ("1.00000000000000000001", "1", Ordering::Greater),
+            ("1e-9223372036854775807", "1e-5", Ordering::Less),
+        ];
+        for (a, b, expected) in tests {
+            let (da, db) = (Decimal::parse(a).unwrap(), Decimal::parse(b).unwrap());
+            assert_eq!(da.cmp(&db), expected, "{a} <> {b}");
+        }
+    }
+
+    #[test]
+    fn test_decimal_parse() {
+        for s in [
+            "",
+            "-",
+            "0x",
+            "1.2.3",
+            "1e",
+            "abc",
+            "0xfg",
+            "0b12",
+            "u32",
+            "NaN",
+            "inf",
+            "1e9223372036854775807",
+            "0.001e-9223372036854775808",
+        ] {
+            assert_eq!(Decimal::parse(s), None, "{s}");
+        }
+        let tests = [
+            ("0x7f1bcd0b0d40", "139757380898112"),
+            ("0xffffffffffffffffff", "4722366482869645213695"),
+            ("1.50", "1.5"),
+            ("-0.001", "-0.001"),
+            ("1e3", "1000"),
+            ("1.5e-3", "0.0015"),
+            ("000", "0"),
+            ("0b1010", "10"),
+            ("0o755", "493"),
+            ("1_000_000", "1000000"),
+            ("5u32", "5"),
+            ("-1.5f64", "-1.5"),
+            ("10L", "10"),
+            ("+.5", "0.5"),
+            ("1e20", "100000000000000000000"),
+            ("1E+10000000000L", "1e10000000000"),
+            ("-12.5e-30", "-1.25e-29"),
+            ("1e-9223372036854775807", "1e-9223372036854775807"),
+        ];
+        for (s, expected) in tests {
+            assert_eq!(Decimal::parse(s).unwrap().to_plain_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use termcolor::ColorChoice;
+
+/// Which detector is used to find structured data in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Detector {
+    /// [`DetectDataIter`](crate::DetectDataIter): fast, but may be fooled by unbalanced brackets
+    #[default]
+    Fast,
+    /// [`DetectDataV2`](crate::DetectDataV2): slower, but yields only properly nested sequences
+    Robust,
+}
+
+/// Output format of [`KrasValue::render`](crate::KrasValue::render)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// detected data is pretty-printed as is
+    #[default]
+    Pretty,
+    /// detected data is converted to JSON, see [`KrasValue::to_json`](crate::KrasValue::to_json)
+    Json,
+    /// detected data is converted to JSON with [`KrasValue::to_json`](crate::KrasValue::to_json) and written as
+    /// block-style YAML
+    Yaml,
+}
+
+/// Options for [`parse_str`](crate::parse_str)
+///
+/// ```
+/// let opts = kras::ParseOptions::new().sort(true).recursive(true);
+/// let value = kras::parse_str(r#"{"b": 1, "a": 2}"#, &opts);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// sort keys of dicts
+    pub sort:      bool,
+    /// try to parse data nested in strings
+    pub recursive: bool,
+    pub detector:  Detector,
+    /// parse data that is cut at the end of input: auto-close open brackets and strings and mark the
+    /// cut with [`KrasValue::Truncated`](crate::KrasValue::Truncated)
+    pub recover:   bool,
+    /// decode url-encoded, hex and base64 payloads in strings and raw text and parse the result, see
+    /// [`KrasValue::Decoded`](crate::KrasValue::Decoded). Strings are parsed as with `recursive`
+    pub decode:    bool,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sort(mut self, sort: bool) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    pub fn detector(mut self, detector: Detector) -> Self {
+        self.detector = detector;
+        self
+    }
+
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    pub fn decode(mut self, decode: bool) -> Self {
+        self.decode = decode;
+        self
+    }
+}
+
+/// Options for [`KrasValue::render`](crate::KrasValue::render)
+///
+/// ```
+/// let opts = kras::RenderOptions::new().indent(4).width(120).color(kras::ColorChoice::Always);
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct RenderOptions {
+    /// indentation of nested values. 0 keeps every value on a single line
+    pub indent:   usize,
+    /// maximum width of output
+    pub width:    usize,
+    pub color:    ColorChoice,
+    pub format:   OutputFormat,
+    /// show strings with escaped newlines decoded, as an indented block of lines under their key.
+    /// The output is not valid data anymore. Has no effect with indent 0
+    pub unescape: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            indent:   2,
+            width:    80,
+            color:    ColorChoice::Never,
+            format:   OutputFormat::Pretty,
+            unescape: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn unescape(mut self, unescape: bool) -> Self {
+        self.unescape = unescape;
+        self
+    }
+
+    // the width passed to the pretty printer. With indent disabled nothing should be wrapped
+    pub(crate) fn line_width(&self) -> usize {
+        if self.indent == 0 {
+            usize::MAX
+        }
+        else {
+            self.width
+        }
+    }
+}
@@ -1,14 +1,24 @@
-use std::{iter::FromIterator, str::FromStr};
+use std::{iter::FromIterator, str::FromStr, sync::OnceLock};
 
 use pom::parser::*;
+use regex::Regex;
 
-use crate::{detect::DetectDataIter, detect2::DetectDataV2, pretty_value::*, stopwatch::Stopwatch};
+use crate::{
+    decode::DECODERS,
+    detect::{get_close, get_open, skip_comment, DetectDataIter, StringTracker},
+    detect2::DetectDataV2,
+    json::json_escape,
+    number::SUFFIXES,
+    options::{Detector, ParseOptions},
+    pretty_value::*,
+    stopwatch::Stopwatch,
+};
 
 fn space<'a>() -> Parser<'a, char, ()> {
     one_of(" \t\r\n").repeat(0..).discard()
 }
 
-fn ident<'a>() -> Parser<'a, char, String> {
+fn name<'a>() -> Parser<'a, char, String> {
     let first = is_a(|c: char| c.is_alphabetic()) | one_of("_%$@\\/");
 
     fn alnum<'a>() -> Parser<'a, char, String> {
@@ -20,27 +30,78 @@ fn ident<'a>() -> Parser<'a, char, String> {
     // [a-z] [a-z0-9]* ([:.]+[a-z0-9]+)*
     let ident = first + alnum().repeat(0..) + (dot.repeat(1..) + alnum().repeat(1..)).repeat(0..);
 
-    ident.collect().map(String::from_iter) - space()
+    ident.collect().map(String::from_iter)
+}
+
+fn ident<'a>() -> Parser<'a, char, String> {
+    name() - space()
+}
+
+// digits with optional underscores between them: 1_000_000
+fn digits<'a>(radix: u32) -> Parser<'a, char, ()> {
+    let digit = move || is_a(move |c: char| c.is_digit(radix));
+    (digit() + (sym('_').opt() + digit()).repeat(0..)).discard()
+}
+
+// type suffix: 5u32, 1.0f64, 10L. Not a part of a word: 3days is not 3d
+fn suffix<'a>() -> Parser<'a, char, ()> {
+    let suffix = SUFFIXES[1..].iter().fold(word(SUFFIXES[0]), |p, s| p | word(s));
+    suffix - !is_a(|c: char| c.is_alphanumeric() || c == '_')
+}
+
+// the text of a number literal without underscores, suffix and leading '+', as f64 reads it
+fn number_value(s: &str) -> Option<f64> {
+    let s = s.replace('_', "");
+    let s = s.strip_prefix('+').unwrap_or(&s);
+    let number_end = |s: &&str| s.ends_with(|c: char| c.is_ascii_digit() || c == '.');
+    let s = SUFFIXES
+        .iter()
+        .find_map(|suffix| s.strip_suffix(suffix).filter(number_end))
+        .unwrap_or(s);
+    f64::from_str(s).ok()
 }
 
 fn plain_number<'a>() -> Parser<'a, char, (f64, String)> {
-    let integer = (one_of("123456789") - one_of("0123456789").repeat(0..)) | sym('0');
-    let frac = sym('.') + one_of("0123456789").repeat(1..);
-    let exp = one_of("eE") + one_of("+-").opt() + one_of("0123456789").repeat(1..);
-    let number = sym('-').opt() + integer + frac.opt() + exp.opt();
+    let integer =
+        (one_of("123456789") + (sym('_').opt() + one_of("0123456789")).repeat(0..)).discard() | sym('0').discard();
+    let frac = || sym('.') + digits(10);
+    let exp = one_of("eE") + one_of("+-").opt() + digits(10);
+    let mantissa = (integer + frac().opt()).discard() | frac().discard();
+    let number = one_of("+-").opt() + mantissa + exp.opt() + suffix().opt();
     let repr = number.collect().map(String::from_iter);
-    repr.convert(|s| f64::from_str(&s).map(|n| (n, s)))
+    repr.convert(|s| number_value(&s).map(|n| (n, s)).ok_or("not a number"))
 }
 
-fn hex_number<'a>() -> Parser<'a, char, (f64, String)> {
-    let prefix = sym('0') + one_of("xX");
-    let integer = one_of("0123456789abcdefABCDEF");
-    let hex = (prefix + integer.repeat(1..)).collect().map(String::from_iter);
-    hex.convert(|s| u64::from_str_radix(&s[2..], 16).map(|n| (n as f64, s)))
+// 0xff, 0b1010, 0o755
+fn radix_number<'a>() -> Parser<'a, char, (f64, String)> {
+    let prefix = |c: &'static str, radix: u32| (sym('0') * one_of(c) * digits(radix)).discard();
+    let number = sym('-').opt() + (prefix("xX", 16) | prefix("oO", 8) | prefix("bB", 2));
+    let repr = number.collect().map(String::from_iter);
+    // f64 is only an approximation; OrdF64 compares numbers by the exact value of the text
+    repr.map(|s| {
+        let digits = s.trim_start_matches('-').replace('_', "");
+        let radix = match &digits[..2] {
+            "0x" | "0X" => 16,
+            "0o" | "0O" => 8,
+            _ => 2,
+        };
+        let n = digits[2..]
+            .chars()
+            .fold(0.0, |n, c| n * radix as f64 + c.to_digit(radix).unwrap() as f64);
+        (if s.starts_with('-') { -n } else { n }, s)
+    })
+}
+
+// NaN, Infinity, -inf, +Inf
+fn special_number<'a>() -> Parser<'a, char, (f64, String)> {
+    let name = word("Infinity") | word("inf") | word("Inf") | word("NaN") | word("nan");
+    let number = one_of("+-").opt() + name - !is_a(|c: char| c.is_alphanumeric() || c == '_');
+    let repr = number.collect().map(String::from_iter);
+    repr.convert(|s| number_value(&s).map(|n| (n, s)).ok_or("not a number"))
 }
 
 fn number<'a>() -> Parser<'a, char, (f64, String)> {
-    hex_number() | plain_number()
+    radix_number() | plain_number() | special_number()
 }
 
 fn x_char<'a>() -> Parser<'a, char, char> {
@@ -61,8 +122,17 @@ fn json_unicode<'a>() -> Parser<'a, char, char> {
         .convert(|n| std::char::from_u32(n).ok_or("not a valid unicode"))
 }
 
+fn rust_unicode<'a>() -> Parser<'a, char, char> {
+    // parse '\u{1F600}'
+    let hex = one_of("0123456789abcdefABCDEF");
+    let ch = sym('u') * sym('{') * hex.repeat(1..7).map(String::from_iter) - sym('}');
+    ch.convert(|s| u32::from_str_radix(&s, 16))
+        .convert(|n| std::char::from_u32(n).ok_or("not a valid unicode"))
+}
+
 fn special_char<'a>() -> Parser<'a, char, char> {
     json_unicode()
+        | rust_unicode()
         | x_char()
         | sym('\\')
         | sym('/')
@@ -73,33 +143,71 @@ fn special_char<'a>() -> Parser<'a, char, char> {
         | sym('n').map(|_| '\n')
         | sym('r').map(|_| '\r')
         | sym('t').map(|_| '\t')
+        | sym('0').map(|_| '\0')
+}
+
+// the body of a string is kept as written, escape sequences are decoded by `unescape`
+fn quoted<'a>(quote: &'static [char]) -> Parser<'a, char, (String, String)> {
+    let escape_sequence = sym('\\') + any();
+    let body = (escape_sequence.discard() | (!seq(quote) * any()).discard()).repeat(0..);
+    let string = seq(quote) * body.collect().map(String::from_iter) - seq(quote);
+    string.map(move |s| (String::from_iter(quote), s))
 }
 
-fn qqstring<'a>() -> Parser<'a, char, (char, String)> {
-    let escape_sequence = sym('\\') * special_char();
-    let string = sym('"') + (none_of("\\\"") | escape_sequence).repeat(0..) - sym('"');
-    string.map(|(a, b)| (a, b.iter().collect()))
+// rust raw string: r#"a "quoted" text"#. There are no escapes, the string ends at the quote
+// followed by the same number of hashes
+fn hashed<'a>() -> Parser<'a, char, (String, String)> {
+    let open = sym('#').repeat(1..) - sym('"');
+    open >> |hashes: Vec<char>| {
+        let n = hashes.len();
+        let close = move || sym('"') * sym('#').repeat(n);
+        let body = (!close() * any()).repeat(0..).map(String::from_iter) - close();
+        body.map(move |s| ("#".repeat(n) + "\"", s))
+    }
 }
 
-fn qstring<'a>() -> Parser<'a, char, (char, String)> {
-    let escape_sequence = sym('\\') * special_char();
-    let string = sym('\'') + (none_of("\\\'") | escape_sequence).repeat(0..) - sym('\'');
-    string.map(|(a, b)| (a, b.iter().collect()))
+// js template literal: `a ${b} c`. The interpolation may contain braces, strings and other templates
+fn template<'a>() -> Parser<'a, char, (String, String)> {
+    fn code<'a>() -> Parser<'a, char, ()> {
+        let braces = sym('{') * call(code).repeat(0..) - sym('}');
+        call(string).discard() | braces.discard() | none_of("{}").discard()
+    }
+    let interpolation = seq(&['$', '{']) * call(code).repeat(0..) - sym('}');
+    let escape_sequence = sym('\\') + any();
+    let body = (escape_sequence.discard() | interpolation.discard() | none_of("`").discard()).repeat(0..);
+    let string = sym('`') * body.collect().map(String::from_iter) - sym('`');
+    string.map(|s| ("`".to_string(), s))
 }
 
 fn string<'a>() -> Parser<'a, char, KrasValue> {
-    // TODO if \x | \u char is not printable - keep it as is ('\x00')
-
+    // b"foo", r'\d+', f"{x}", u'\u044f', br#"foo"#, 'a', `${a}`
     fn alpha<'a>() -> Parser<'a, char, String> {
         let is_alpha = is_a(|c: char| c.is_alphabetic());
         is_alpha.repeat(0..).collect().map(String::from_iter)
     }
 
-    let string = alpha() + (qqstring() | qstring());
+    // python triple-quoted strings go first: '' is an empty string only if it's not followed by another '
+    let quoted = quoted(&['"', '"', '"']) | quoted(&['\'', '\'', '\'']) | quoted(&['"']) | quoted(&['\'']);
+    let string = alpha() + (hashed() | template() | quoted);
 
     string.map(|(p, (q, s))| KrasValue::Str((q, p, s)))
 }
 
+// decode escape sequences of a string body. Raw strings (r"..") are kept as is, unknown
+// escape sequences too
+pub(crate) fn unescape(prefix: &str, body: &str) -> String {
+    if prefix.contains(['r', 'R']) {
+        return body.to_string();
+    }
+    let buf = body.chars().collect::<Vec<_>>();
+    let escape_sequence = (sym('\\') * special_char()).map(|c| vec![c]);
+    let unknown = (sym('\\') + any()).map(|(a, b)| vec![a, b]);
+    let chars = (escape_sequence | unknown | any().map(|c| vec![c])).repeat(0..);
+    chars
+        .parse(&buf)
+        .map_or_else(|_| body.to_string(), |r| r.into_iter().flatten().collect())
+}
+
 fn pair_delim<'a>() -> Parser<'a, char, String> {
     let delim = space() * (seq(&[':']) | seq(&['=', '>']) | seq(&['='])) - space();
     delim.map(String::from_iter)
@@ -110,9 +218,33 @@ fn array_delim<'a>() -> Parser<'a, char, String> {
     delim.map(|c| c.to_string())
 }
 
+fn comment<'a>() -> Parser<'a, char, String> {
+    // "// foo", "# foo", "/* foo */"
+    let url = is_a(|c: char| c.is_alphanumeric());
+    let line_start = (seq(&['/', '/']) - !url).discard() | (sym('#') + one_of(" \t")).discard();
+    let line = line_start + none_of("\r\n").repeat(0..);
+    let block = seq(&['/', '*']) + (!seq(&['*', '/']) * any()).repeat(0..) + seq(&['*', '/']);
+    let comment = line.discard() | block.discard();
+    comment.collect().map(String::from_iter) - space()
+}
+
+// a keyword after a space is the next item, not a kv delimiter: #{:a :b}, [x :y]
+fn next_keyword<'a>() -> Parser<'a, char, ()> {
+    Parser::new(|input: &'a [char], start: usize| {
+        let after_space = start > 0 && input[start - 1].is_whitespace();
+        match after_space {
+            true => atom().parse_at(input, start).map(|_| ((), start)),
+            false => Err(pom::Error::Mismatch {
+                message:  "no space before keyword".to_string(),
+                position: start,
+            }),
+        }
+    })
+}
+
 fn list_item<'a>() -> Parser<'a, char, KrasValue> {
-    let delim = call(inner_value) + (array_delim() | pair_delim()).opt();
-    delim.map(|(a, b)| KrasValue::ListItem((Box::new(a), b)))
+    let delim = call(inner_value) + (array_delim() | (!next_keyword() * pair_delim())).opt();
+    comment().map(KrasValue::Comment) | delim.map(|(a, b)| KrasValue::ListItem((Box::new(a), b)))
 }
 
 fn array<'a>() -> Parser<'a, char, (String, Vec<KrasValue>, String)> {
@@ -126,33 +258,400 @@ fn array<'a>() -> Parser<'a, char, (String, Vec<KrasValue>, String)> {
 }
 
 fn constructor<'a>() -> Parser<'a, char, KrasValue> {
-    let res = ident() - space() + array();
+    // only a struct body may be separated by a space: Foo { a: 1 }. In (foo [x]) and (foo (bar)) the
+    // list is the next item
+    let struct_body = space() - -one_of("{(");
+    let res = name() - struct_body.opt() + array();
     res.map(|(a, b)| KrasValue::Constructor((Box::new(KrasValue::Ident(a)), Box::new(KrasValue::List(b)))))
 }
 
+// go %v and %+v: map[a:1 b:[x y]], &{Name:foo Age:3}. Items are separated by spaces, keys are
+// followed by ':' with no space. Returns list items, key and value are separate items
+fn go_items<'a>() -> Parser<'a, char, Vec<KrasValue>> {
+    let first = is_a(|c: char| c.is_alphabetic() || c == '_');
+    let key = (first + is_a(|c: char| c.is_alphanumeric() || c == '_').repeat(0..)).collect();
+    let key = key.map(String::from_iter);
+    let field = key - sym(':') - !one_of(" \t\r\n:/") + call(inner_value);
+    let field = field.map(|(k, v)| {
+        vec![
+            KrasValue::ListItem((Box::new(KrasValue::Ident(k)), Some(":".to_string()))),
+            KrasValue::ListItem((Box::new(v), None)),
+        ]
+    });
+    // a value followed by ':' is a key of json or elixir: {"a":"b"} is not "a" and :"b"
+    let value = call(inner_value) - !sym(':');
+    let item = field | value.map(|v| vec![KrasValue::ListItem((Box::new(v), None))]);
+    let items = space() * item.repeat(0..);
+    items.map(|items| items.into_iter().flatten().collect())
+}
+
+// go %v has no delimiters between items and no space after a key: {Name:foo Tags:[a b]}. Braces with
+// anything else are not tried as go: nested braces would be parsed twice, by go and by array, at every level
+fn is_go_struct(rest: &[char]) -> bool {
+    let mut strings = StringTracker::default();
+    let mut depth = 0;
+    for (i, c) in rest.iter().enumerate() {
+        let after_quote = i > 0 && matches!(rest[i - 1], '"' | '\'');
+        if strings.skip(rest, i) {
+            continue;
+        }
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' if depth <= 1 => return true,
+            '}' | ']' | ')' => depth -= 1,
+            ',' | ';' | '=' if depth == 1 => return false,
+            // {a: 1}, {"a":1}
+            ':' if depth == 1 && (after_quote || rest.get(i + 1).filter(|c| !c.is_whitespace()).is_none()) => {
+                return false
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+fn go_value<'a>() -> Parser<'a, char, KrasValue> {
+    fn list(open: &str, items: Vec<KrasValue>, close: &str) -> KrasValue {
+        KrasValue::List((open.to_string(), items, close.to_string()))
+    }
+    fn constructor(name: &str, args: KrasValue) -> KrasValue {
+        KrasValue::Constructor((Box::new(KrasValue::Ident(name.to_string())), Box::new(args)))
+    }
+    let map = seq(&['m', 'a', 'p', '[']) * go_items() - sym(']');
+    let pointer = seq(&['&', '{']) * go_items() - sym('}');
+    let go_struct = sym('{') * go_items() - sym('}');
+    let nil = seq(&['<', 'n', 'i', 'l', '>']).map(|_| KrasValue::Ident("<nil>".to_string()));
+    map.map(|items| constructor("map", list("[", items, "]")))
+        | pointer.map(|items| constructor("&", list("{", items, "}")))
+        | go_struct.map(|items| list("{", items, "}"))
+        | nil
+}
+
+// a literal word
+fn word<'a>(word: &'static str) -> Parser<'a, char, ()> {
+    word.chars().fold(empty(), |p, c| (p + sym(c)).discard())
+}
+
+// unquoted value of php print_r and java toString: a number or a text
+fn unquoted(s: &str) -> KrasValue {
+    let chars = s.chars().collect::<Vec<_>>();
+    let num = (number() - end()).parse(&chars);
+    match num {
+        Ok((n, r)) => KrasValue::Num(OrdF64(n, r)),
+        Err(_) => KrasValue::Ident(s.to_string()),
+    }
+}
+
+fn php_pair(key: KrasValue, value: KrasValue) -> Vec<KrasValue> {
+    vec![
+        KrasValue::ListItem((Box::new(key), Some("=>".to_string()))),
+        KrasValue::ListItem((Box::new(value), None)),
+    ]
+}
+
+fn php_array(name: String, open: &str, items: Vec<Vec<KrasValue>>, close: &str) -> KrasValue {
+    let items = items.into_iter().flatten().collect();
+    let args = KrasValue::List((open.to_string(), items, close.to_string()));
+    KrasValue::Constructor((Box::new(KrasValue::Ident(name)), Box::new(args)))
+}
+
+// php print_r: Array ( [a] => 1 [b] => Array ( [0] => x y ) ), Foo Object ( [bar:protected] => 1 ).
+// Values are not quoted, a value lasts until the next key, the end of line or the end of array
+fn print_r<'a>() -> Parser<'a, char, KrasValue> {
+    let header = word("Array").map(|_| "Array".to_string()) | (ident() - word("Object"));
+    let key = sym('[') * none_of("]\r\n").repeat(0..).map(String::from_iter) - sym(']');
+    let key_end = || sym('[') * none_of("]\r\n").repeat(0..) * sym(']') * space() * word("=>");
+    // ")" ends the array only if it's followed by another key or the end of parent array
+    let array_end = sym(')') * space() * (key_end() | sym(')').discard() | end());
+    let item_end = space() * (key_end() | array_end);
+    let text = (!item_end * none_of("\r\n")).repeat(0..).map(String::from_iter);
+    let value = call(print_r) | text.map(|s| unquoted(s.trim_end()));
+    let item = key.map(|k| unquoted(&k)) - space() - word("=>") - one_of(" \t").repeat(0..) + value - space();
+    let array = header - space() - sym('(') - space() + item.map(|(k, v)| php_pair(k, v)).repeat(0..) - sym(')');
+    array.map(|(name, items)| php_array(name, "(", items, ")"))
+}
+
+// php var_dump string(3) "abc": the content is not escaped, its length is given in bytes
+fn var_dump_string<'a>() -> Parser<'a, char, KrasValue> {
+    let len = one_of("0123456789").repeat(1..).map(String::from_iter);
+    let len = word("string(") * len.convert(|s| usize::from_str(&s));
+    let content = (len - sym(')') - space() - sym('"'))
+        >> |len: usize| {
+            Parser::new(move |input: &'a [char], start: usize| {
+                let (mut end, mut bytes) = (start, 0);
+                while bytes < len {
+                    let c = input.get(end).ok_or(pom::Error::Incomplete)?;
+                    bytes += c.len_utf8();
+                    end += 1;
+                }
+                Ok((String::from_iter(&input[start..end]), end))
+            })
+        };
+    (content - sym('"')).map(|s| KrasValue::Str(("\"".to_string(), "".to_string(), json_escape(&s))))
+}
+
+// php var_dump: array(2) { ["a"]=> int(1) [0]=> string(3) "abc" }, object(Foo)#1 (1) { ["bar":protected]=> NULL }.
+// Typed scalars are converted to plain values
+fn var_dump<'a>() -> Parser<'a, char, KrasValue> {
+    let count = sym('(') * one_of("0123456789").repeat(1..) * sym(')');
+    let object = word("object(") * none_of(")").repeat(1..).map(String::from_iter) - sym(')');
+    let object = object - (sym('#') * one_of("0123456789").repeat(1..)).opt() - space();
+    let header = word("array").map(|_| "array".to_string()) | object;
+    let typed = |name: &'static str| word(name) * sym('(') * none_of(")").repeat(0..).map(String::from_iter) - sym(')');
+    let scalar = typed("int") | typed("float") | typed("bool") | word("NULL").map(|_| "NULL".to_string());
+    let value = call(var_dump) | var_dump_string() | scalar.map(|s| unquoted(&s));
+    let str_key = sym('"') * none_of("\"").repeat(0..).map(String::from_iter) - sym('"');
+    let str_key = str_key.map(|s| KrasValue::Str(("\"".to_string(), "".to_string(), json_escape(&s))));
+    let num_key = (sym('-').opt() + one_of("0123456789").repeat(1..))
+        .collect()
+        .map(String::from_iter);
+    // property visibility is dropped: ["bar":"Foo":private]
+    let visibility = (sym(':') * none_of(":]").repeat(1..)).repeat(0..);
+    let key = sym('[') * (str_key | num_key.map(|s| unquoted(&s))) - visibility - sym(']');
+    let item = key - space() - word("=>") - space() + value - space();
+    let items = item.map(|(k, v)| php_pair(k, v)).repeat(0..);
+    let array = header - count - space() - sym('{') - space() + items - sym('}');
+    array.map(|(name, items)| php_array(name, "{", items, "}"))
+}
+
+// erlang and elixir atoms, ruby symbols, clojure keywords: :ok, :"quoted atom", :valid?, :user/first-name, ::local
+fn atom<'a>() -> Parser<'a, char, KrasValue> {
+    let first = is_a(|c: char| c.is_alphabetic()) | sym('_');
+    let name = first + (is_a(|c: char| c.is_alphanumeric()) | one_of("_?!@.-/*+")).repeat(0..);
+    let atom = sym(':') + sym(':').opt() + (name.discard() | quoted(&['"']).discard());
+    atom.collect().map(|s| KrasValue::Atom(String::from_iter(s))) - space()
+}
+
+// lisp symbols made of operator chars: (+ 1 2), (-> x f), (<= a b). A symbol can't start with '>': it
+// would eat the end of <a> and <<"bin">>. '=' and '=>' are kv delimiters: { x = 1 }
+fn symbol<'a>() -> Parser<'a, char, String> {
+    let symbol = !sym('>') * one_of("+-*/<>=!?&").repeat(1..) - !is_a(|c: char| c.is_alphanumeric());
+    let symbol = symbol.collect().map(String::from_iter);
+    symbol.convert(|s| {
+        if s == "=" || s == "=>" {
+            Err(s)
+        }
+        else {
+            Ok(s)
+        }
+    }) - space()
+}
+
+// edn tagged literals: #inst "1985-04-12T23:20:50.52Z", #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6".
+// The tag must be followed by a space: #name{...} is not a tagged literal
+fn tagged<'a>() -> Parser<'a, char, KrasValue> {
+    let name = is_a(|c: char| c.is_alphabetic()) + (is_a(|c: char| c.is_alphanumeric()) | one_of("_.-/")).repeat(0..);
+    let tag = (sym('#') + name).collect().map(String::from_iter) - one_of(" \t").repeat(1..);
+    let tagged = tag + call(inner_value);
+    tagged.map(|(t, v)| KrasValue::Constructor((Box::new(KrasValue::Ident(t)), Box::new(v))))
+}
+
+// java and kotlin toString, lombok and java records: User(id=1, name=Bob Smith, tags=[a, b]), User{id=1,
+// name='Bob'}, Point[x=1, y=2]. Values are not quoted: a value that is not followed by ',' or the end of
+// record is a text that lasts until the next key
+fn record<'a>() -> Parser<'a, char, KrasValue> {
+    fn body<'a>(open: char, close: char) -> Parser<'a, char, (String, Vec<KrasValue>, String)> {
+        let key = || name() - space() - sym('=') - space();
+        let item_end = move || space() * ((sym(',') * space() * key().discard()) | sym(close).discard());
+        let text = (!item_end() * none_of("\r\n")).repeat(0..);
+        let text = text.map(|s| unquoted(String::from_iter(s).trim_end()));
+        let value = (call(value) - -(sym(',') | sym(close))) | (text - space());
+        let item = key() + value + array_delim().opt();
+        let item = item.map(|((k, v), d)| {
+            vec![
+                KrasValue::ListItem((Box::new(KrasValue::Ident(k)), Some("=".to_string()))),
+                KrasValue::ListItem((Box::new(v), d)),
+            ]
+        });
+        let body = sym(open) * space() * item.repeat(1..) - sym(close);
+        body.map(move |items| {
+            (
+                open.to_string(),
+                items.into_iter().flatten().collect(),
+                close.to_string(),
+            )
+        })
+    }
+    let record = name() - (space() - -sym('{')).opt() + (body('(', ')') | body('{', '}') | body('[', ']'));
+    record.map(|(a, b)| KrasValue::Constructor((Box::new(KrasValue::Ident(a)), Box::new(KrasValue::List(b)))))
+}
+
+// ruby inspect: #<User id: 1, name: "x">, #<User:0x000055d5 @id=1>, #<Set: {1, 2}>. The class name
+// (and the object address) is the constructor, attributes are its args
+fn ruby_object<'a>() -> Parser<'a, char, KrasValue> {
+    let class = (is_a(|c: char| c.is_alphanumeric()) | one_of("_:"))
+        .repeat(1..)
+        .map(String::from_iter);
+    let object = seq(&['#', '<']) * class - space() + list_item().repeat(0..) - sym('>');
+    object.map(|(class, items)| {
+        let args = KrasValue::List(("#<".to_string(), items, ">".to_string()));
+        KrasValue::Constructor((Box::new(KrasValue::Ident(class)), Box::new(args)))
+    })
+}
+
+// erlang maps #{a => 1}, elixir maps %{a: 1} and binaries <<"abc">>
+fn beam<'a>() -> Parser<'a, char, KrasValue> {
+    let map = (seq(&['#', '{']) | seq(&['%', '{'])) + space() * list_item().repeat(0..) + seq(&['}']);
+    let binary = seq(&['<', '<']) + space() * list_item().repeat(0..) + seq(&['>', '>']);
+    (map | binary).map(|((o, items), c)| KrasValue::List((String::from_iter(o), items, String::from_iter(c))))
+}
+
+fn xml_name<'a>() -> Parser<'a, char, String> {
+    let first = is_a(|c: char| c.is_alphabetic()) | one_of("_:");
+    let name = first + (is_a(|c: char| c.is_alphanumeric()) | one_of("_:.-")).repeat(0..);
+    name.collect().map(String::from_iter)
+}
+
+// attributes are kept as written: there are no backslash escapes in xml
+fn xml_attr<'a>() -> Parser<'a, char, KrasValue> {
+    let dq = sym('"') * none_of("\"").repeat(0..).map(String::from_iter) - sym('"');
+    let sq = sym('\'') * none_of("'").repeat(0..).map(String::from_iter) - sym('\'');
+    let value = dq.map(|s| ("\"", s)) | sq.map(|s| ("'", s));
+    let attr = xml_name() - space() - sym('=') - space() + value;
+    attr.map(|(k, (q, v))| {
+        let v = KrasValue::Str((q.to_string(), "".to_string(), v));
+        KrasValue::Pair((Box::new(KrasValue::Ident(k)), "=".to_string(), Box::new(v), None))
+    })
+}
+
+// text between tags, comments and cdata. Whitespace between tags is only an indentation and is dropped
+fn xml_node<'a>() -> Parser<'a, char, Option<KrasValue>> {
+    let comment = seq(&['<', '!', '-', '-']) + (!seq(&['-', '-', '>']) * any()).repeat(0..) + seq(&['-', '-', '>']);
+    let comment = comment
+        .collect()
+        .map(|s| Some(KrasValue::Comment(String::from_iter(s))));
+    let cdata_start = seq(&['<', '!', '[', 'C', 'D', 'A', 'T', 'A', '[']);
+    let cdata = cdata_start + (!seq(&[']', ']', '>']) * any()).repeat(0..) + seq(&[']', ']', '>']);
+    let cdata = cdata.collect().map(|s| Some(KrasValue::Text(String::from_iter(s))));
+    let text = none_of("<").repeat(1..).map(String::from_iter);
+    let text = text.map(|s| {
+        if s.trim().is_empty() {
+            None
+        }
+        else {
+            Some(KrasValue::Text(s))
+        }
+    });
+    call(xml_element).map(Some) | comment | cdata | text
+}
+
+// html elements that have no end tag: <br>, <img src="x">
+fn is_void(name: &str) -> bool {
+    let void = [
+        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+    ];
+    void.contains(&name.to_lowercase().as_str())
+}
+
+// xml and html: <a href="x">text <b>bold</b></a>, <br/>. The end tag must match the start tag
+fn xml_element<'a>() -> Parser<'a, char, KrasValue> {
+    let attrs = (one_of(" \t\r\n").repeat(1..) * xml_attr()).repeat(0..);
+    let start = sym('<') * xml_name() + attrs - space() + (seq(&['/', '>']) | seq(&['>'])).map(String::from_iter);
+    start
+        >> |((name, attrs), end): ((String, Vec<KrasValue>), String)| {
+            if end == "/>" || is_void(&name) {
+                let element = KrasValue::Element((name, attrs, end, None));
+                return empty().map(move |_| element.clone());
+            }
+            let tag = name.clone();
+            let end_tag = seq(&['<', '/'])
+                * xml_name().convert(move |n| {
+                    if n == tag {
+                        Ok(())
+                    }
+                    else {
+                        Err(n)
+                    }
+                });
+            let element = xml_node().repeat(0..) - end_tag - space() - sym('>');
+            element.map(move |nodes| {
+                let children = nodes.into_iter().flatten().collect();
+                KrasValue::Element((name.clone(), attrs.clone(), end.clone(), Some(children)))
+            })
+        }
+}
+
 fn inner_value<'a>() -> Parser<'a, char, KrasValue> {
-    value() | ident().map(KrasValue::Ident)
+    let word = Parser::new(|input: &'a [char], start: usize| {
+        let parser = match input.get(start) {
+            Some(':') => atom(),
+            _ => ident().map(KrasValue::Ident) | symbol().map(KrasValue::Ident),
+        };
+        parser.parse_at(input, start)
+    });
+    value() | word
+}
+
+fn num<'a>() -> Parser<'a, char, KrasValue> {
+    number().map(|(n, r)| KrasValue::Num(OrdF64(n, r)))
+}
+
+fn list_value<'a>() -> Parser<'a, char, KrasValue> {
+    array().map(|(s, arr, c)| KrasValue::List((s, arr, c)))
+}
+
+fn starts_with(chars: &[char], s: &str) -> bool {
+    chars.len() >= s.len() && s.chars().zip(chars).all(|(a, b)| a == *b)
 }
 
+type ValueParser<'a> = fn() -> Parser<'a, char, KrasValue>;
+
+// values that start with a word: b"bytes", NaN, Array ( .. ), array(1) { .. }, map[a:1], User(id=1), Foo { a: 1 }
+fn named_value<'a>(rest: &[char]) -> Parser<'a, char, KrasValue> {
+    let prefix = rest.iter().take_while(|c| c.is_alphabetic()).count();
+    let name = rest
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || "_%$@\\/.:-".contains(**c))
+        .count();
+    let spaces = rest[name..].iter().take_while(|c| c.is_whitespace()).count();
+    let is_object = spaces > 0 && starts_with(&rest[name + spaces..], "Object");
+    let parsers: [(bool, ValueParser<'a>); 7] = [
+        (matches!(rest.get(prefix), Some('"' | '\'' | '`' | '#')), string),
+        (matches!(rest[0], 'I' | 'i' | 'N' | 'n'), num),
+        (starts_with(rest, "Array") || is_object, print_r),
+        (starts_with(rest, "array(") || starts_with(rest, "object("), var_dump),
+        (starts_with(rest, "map["), go_value),
+        (true, record),
+        (true, constructor),
+    ];
+    let parsers = parsers.into_iter().filter(|(ok, _)| *ok).map(|(_, p)| p());
+    parsers.reduce(|a, b| a | b).unwrap()
+}
+
+// value() is called for every item of every list: only the parsers that may match the leading chars are
+// built and tried
 fn value<'a>() -> Parser<'a, char, KrasValue> {
-    (string()
-        | number().map(|(n, r)| KrasValue::Num(OrdF64(n, r)))
-        | constructor()
-        | array().map(|(s, arr, c)| KrasValue::List((s, arr, c))))
-        - space()
+    let value = Parser::new(|input: &'a [char], start: usize| {
+        let rest = &input[start..];
+        let parser = match rest {
+            ['"' | '\'' | '`', ..] | ['#', '"' | '#', ..] => string(),
+            ['#', '<', ..] => ruby_object(),
+            ['#', '{', ..] | ['%', '{', ..] | ['<', '<', ..] => beam(),
+            ['#', c, ..] if c.is_alphabetic() => tagged(),
+            ['0'..='9' | '+' | '-' | '.', ..] => num(),
+            ['[' | '(', ..] => list_value(),
+            ['{', ..] if is_go_struct(rest) => go_value() | list_value(),
+            ['{', ..] => list_value(),
+            ['<', ..] => go_value() | xml_element() | list_value(),
+            ['&', '{', ..] => go_value(),
+            [c, ..] if c.is_alphabetic() => named_value(rest),
+            _ => record() | constructor(),
+        };
+        parser.parse_at(input, start)
+    });
+    value - space()
 }
 
-pub fn kras<'a>() -> Parser<'a, char, KrasValue> {
+pub(crate) fn kras<'a>() -> Parser<'a, char, KrasValue> {
     space() * value() - end()
 }
 
-// bool sort, bool robust
-struct RecursiveStringParser(bool, bool);
+struct RecursiveStringParser<'a>(&'a ParseOptions);
 
-impl KrasVisitor for RecursiveStringParser {
+impl<'a> KrasVisitor for RecursiveStringParser<'a> {
     fn visit_str(&self, val: &mut KrasValue) {
-        if let KrasValue::Str((_, _, ref s)) = val {
-            let mut inner = parse_str(s, self.0, true, self.1);
+        if let KrasValue::Str((_, ref p, ref s)) = val {
+            let mut inner = parse_str(&unescape(p, s), self.0);
             debug!("rec parse: {:?}", inner);
             if let KrasValue::RawList(ref mut items) = inner {
                 if items.is_empty() {
@@ -177,44 +676,323 @@ impl KrasVisitor for RecursiveStringParser {
     }
 }
 
-pub fn parse_str(s: &str, sort: bool, recursive: bool, robust: bool) -> KrasValue {
+// brackets that are left open at the end of input, outermost first, and the string that is left
+// open. '<' is skipped: it's more often a comparison than an unclosed bracket
+fn unclosed(buf: &[char]) -> (Vec<(usize, char)>, StringTracker) {
+    let mut stack = Vec::new();
+    let mut strings = StringTracker::cut();
+    let mut comment_end = 0;
+    for (idx, c) in buf.iter().enumerate() {
+        let c = *c;
+        if idx < comment_end {
+            continue;
+        }
+        if strings.skip(buf, idx) {
+            continue;
+        }
+        if !stack.is_empty() {
+            if let Some(end) = skip_comment(buf, idx) {
+                comment_end = end;
+                continue;
+            }
+        }
+        match c {
+            '(' | '[' | '{' => stack.push((idx, c)),
+            ')' | ']' | '}' => {
+                if stack.last().map(|(_, o)| *o) == Some(get_open(c)) {
+                    stack.pop();
+                }
+                else {
+                    stack.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+    (stack, strings)
+}
+
+// the data is cut in the last item of the innermost list: add the mark there. `depth` is the number of lists
+// that were closed by recover()
+fn mark_truncated(val: &mut KrasValue, depth: usize) -> Option<()> {
+    match val {
+        KrasValue::List((_, items, _)) if depth == 1 => {
+            items.push(KrasValue::ListItem((Box::new(KrasValue::Truncated), None)));
+            Some(())
+        }
+        KrasValue::List((_, items, _)) => mark_truncated(items.last_mut()?, depth - 1),
+        KrasValue::ListItem((v, _)) | KrasValue::Constructor((_, v)) => mark_truncated(v, depth),
+        _ => None,
+    }
+}
+
+// find data that is cut at the end of input, close all open strings and brackets and parse it.
+// Brackets in the text are not data: smile :( got {"a": 1. The innermost '{' or '[' is tried first, then
+// the enclosing brackets while the data is an item of them: {"a": [1, but not (got [1
+fn recover(buf: &[char]) -> Option<(usize, KrasValue)> {
+    let (stack, strings) = unclosed(buf);
+    let parse = |i: usize| {
+        let mut data = buf[stack[i].0..].to_vec();
+        if strings.is_escape() {
+            data.pop();
+        }
+        data.extend(strings.open_quote().unwrap_or_default().chars());
+        data.extend(stack[i..].iter().rev().map(|(_, c)| get_close(*c)));
+        debug!("RECOVER: {}", String::from_iter(data.iter()));
+        let mut r = kras().parse(&data).ok()?;
+        mark_truncated(&mut r, stack.len() - i)?;
+        Some(r)
+    };
+    let is_item = |outer: usize, inner: usize| {
+        let before = String::from_iter(&buf[outer + 1..inner]);
+        let before = before.trim_end();
+        before.is_empty() || before.ends_with([',', ';', ':', '=', '>'])
+    };
+    let start = stack.iter().rposition(|(_, c)| *c == '{' || *c == '[')?;
+    let mut res = (stack[start].0, parse(start)?);
+    for i in (0..start).rev() {
+        if !is_item(stack[i].0, stack[i + 1].0) {
+            break;
+        }
+        match parse(i) {
+            Some(r) => res = (stack[i].0, r),
+            None => break,
+        }
+    }
+    Some(res)
+}
+
+// the detectors only see brackets, but some data starts with a word before them:
+// go map[a:1] and &{Name:foo}, php Array ( [a] => 1 ), Foo Object ( ), array(1) { }, object(Foo)#1 (1) { },
+// array ( 'a' => 1 ), erlang #{a => 1}, elixir %{a: 1} and %User{name: "x"}
+fn data_prefix(buf: &[char], pos: usize) -> usize {
+    static PREFIX: OnceLock<Regex> = OnceLock::new();
+    let prefix = PREFIX.get_or_init(|| {
+        let go = r"&|\bmap";
+        let beam = r"#|%[\w.]*";
+        let print_r = r"\b(?:Array|array|[\w\\]+ Object)\s*";
+        let var_dump = r"\b(?:array|object\([\w\\]+\)#\d+ )\(\d+\) ?";
+        let datetime = r"datetime\.(?:datetime|date|time|timedelta)";
+        let python = format!(r"\b(?:{datetime}|Decimal|UUID|OrderedDict|defaultdict|Counter|deque)");
+        Regex::new(&format!("(?:{go}|{beam}|{print_r}|{var_dump}|{python})$")).unwrap()
+    });
+    // rust Debug structs: Foo { a: 1 }, Foo {\n    a: 1,\n}. A capitalized word before any other data is text
+    static RUST: OnceLock<(Regex, Regex)> = OnceLock::new();
+    let (rust_name, rust_body) = RUST.get_or_init(|| {
+        let name = Regex::new(r"\b(?:\w+::)*[A-Z]\w* $").unwrap();
+        (name, Regex::new(r"^\{\s+[a-z_]\w*: ").unwrap())
+    });
+    // the prefix is short, there is no need to look far back
+    let before = String::from_iter(&buf[pos.saturating_sub(80)..pos]);
+    let body = || String::from_iter(&buf[pos..buf.len().min(pos + 80)]);
+    match prefix.find(&before) {
+        Some(m) => pos - before[m.start()..].chars().count(),
+        None => match rust_name.find(&before) {
+            Some(m) if rust_body.is_match(&body()) => pos - before[m.start()..].chars().count(),
+            _ => pos,
+        },
+    }
+}
+
+// the class name of a java record that starts at `pos`: User(id=1), User {id=1}. Unlike data_prefix, the
+// name is only a part of the data if the record has named fields: Math.max(1, 2) is a call
+fn record_prefix(buf: &[char], pos: usize) -> Option<usize> {
+    static PREFIX: OnceLock<Regex> = OnceLock::new();
+    let prefix = PREFIX.get_or_init(|| Regex::new(r"\b[A-Z]\w* ?$").unwrap());
+    let before = String::from_iter(&buf[pos.saturating_sub(80)..pos]);
+    let m = prefix.find(&before)?;
+    Some(pos - before[m.start()..].chars().count())
+}
+
+// logfmt: level=info user="bob" took=12ms. A value is a string, a number or any text up to a space
+fn logfmt_pair<'a>() -> Parser<'a, char, KrasValue> {
+    let key = is_a(|c: char| c.is_alphanumeric() || "_.-/".contains(c)).repeat(1..);
+    let key = key.collect().map(|k| KrasValue::Ident(String::from_iter(k)));
+    let stop = || -(is_a(char::is_whitespace).discard() | pom::parser::end());
+    let string = quoted(&['"']).map(|(q, s)| KrasValue::Str((q, String::new(), s)));
+    let number = number().map(|(n, r)| KrasValue::Num(OrdF64(n, r)));
+    let text = is_a(|c: char| !c.is_whitespace()).repeat(1..);
+    let text = text.collect().map(|s| KrasValue::Ident(String::from_iter(s)));
+    let value = ((string | number) - stop()) | text;
+    (key - sym('=') + value).map(|(k, v)| KrasValue::Pair((Box::new(k), "=".to_string(), Box::new(v), None)))
+}
+
+// a run of two or more logfmt pairs separated by spaces; a dict without brackets
+fn logfmt<'a>() -> Parser<'a, char, KrasValue> {
+    let pairs = list(logfmt_pair(), sym(' ')).convert(|pairs| {
+        if pairs.len() > 1 {
+            Ok(pairs)
+        }
+        else {
+            Err(())
+        }
+    });
+    pairs.map(|mut pairs| {
+        let last = pairs.len() - 1;
+        for pair in &mut pairs[..last] {
+            if let KrasValue::Pair((_, _, _, ref mut d2)) = pair {
+                *d2 = Some(" ".to_string());
+            }
+        }
+        KrasValue::List((String::new(), pairs, String::new()))
+    })
+}
+
+// split the text between detected values into logfmt runs and raw strings
+fn logfmt_parts(buf: &[char]) -> Vec<KrasValue> {
+    let mut res = Vec::new();
+    let mut start = 0;
+    let mut pos = 0;
+    let parser = logfmt();
+    while pos < buf.len() {
+        // a key starts a word: a=1 b=2, but not x;a=1 b=2
+        let starts_word = pos == 0 || buf[pos - 1].is_whitespace();
+        match starts_word.then(|| parser.parse_at(buf, pos)) {
+            Some(Ok((r, e))) => {
+                if pos > start {
+                    res.push(KrasValue::RawStr(String::from_iter(&buf[start..pos])));
+                }
+                res.push(r);
+                start = e;
+                pos = e;
+            }
+            _ => pos += 1,
+        }
+    }
+    if start < buf.len() {
+        res.push(KrasValue::RawStr(String::from_iter(&buf[start..])));
+    }
+    res
+}
+
+// encoded payloads in raw text: %7B%22a%22%3A1%7D, 7b2261223a317d, eyJhIjoxfQ==. A payload is only
+// replaced with the decoded value if there is some data in it
+fn decoded_parts(s: &str, opts: &ParseOptions) -> Vec<KrasValue> {
+    static PAYLOAD: OnceLock<Regex> = OnceLock::new();
+    let payload = PAYLOAD.get_or_init(|| Regex::new(r"[\w+/%-]{8,}=*").unwrap());
+    let decoded = |payload: &str| {
+        DECODERS.iter().find_map(|(enc, decode)| {
+            let mut value = parse_str(&decode(payload)?, opts);
+            if value.data().is_empty() {
+                return None;
+            }
+            if let KrasValue::RawList(ref mut items) = value {
+                if items.len() == 1 {
+                    value = items.pop().unwrap();
+                }
+            }
+            Some(KrasValue::Decoded((enc.to_string(), Box::new(value))))
+        })
+    };
     let mut res = Vec::new();
-    let buf = s.chars().collect::<Vec<_>>();
     let mut start = 0;
-    let iter: Box<dyn Iterator<Item = (usize, &[char])>> = if robust {
-        Box::new(DetectDataV2::new(&buf))
+    for m in payload.find_iter(s) {
+        if let Some(r) = decoded(m.as_str()) {
+            if m.start() > start {
+                res.push(KrasValue::RawStr(s[start..m.start()].to_string()));
+            }
+            res.push(r);
+            start = m.end();
+        }
     }
-    else {
-        Box::new(DetectDataIter::new(&buf))
+    if start < s.len() {
+        res.push(KrasValue::RawStr(s[start..].to_string()));
+    }
+    res
+}
+
+/// Find and parse all structured data in `s`
+///
+/// Returns a [`KrasValue::RawList`] of parsed values and the raw text between them
+/// Runs of logfmt pairs in the text between them (`level=info took=12ms`) become bracket-less dicts
+pub fn parse_str(s: &str, opts: &ParseOptions) -> KrasValue {
+    let mut res = Vec::new();
+    let buf = s.chars().collect::<Vec<_>>();
+    let mut start = 0;
+    // data truncated at the end of input; the detectors only look at the text before it
+    let recovered = if opts.recover { recover(&buf) } else { None };
+    let end = recovered.as_ref().map_or(buf.len(), |(pos, _)| *pos);
+    let iter: Box<dyn Iterator<Item = (usize, &[char])>> = match opts.detector {
+        Detector::Robust => Box::new(DetectDataV2::new(&buf[..end])),
+        Detector::Fast => Box::new(DetectDataIter::new(&buf[..end])),
+    };
+    // the text between the values: logfmt runs, encoded payloads and raw strings
+    let raw_parts = |text: &[char]| {
+        let parts = logfmt_parts(text).into_iter();
+        parts
+            .flat_map(|part| match part {
+                KrasValue::RawStr(s) if opts.decode => decoded_parts(&s, opts),
+                _ => vec![part],
+            })
+            .collect::<Vec<_>>()
     };
+    let mut found: Vec<(usize, usize, KrasValue)> = Vec::new();
+    let mut xml_end = 0;
     for (pos, data) in iter {
         debug!("DETECT: {}", String::from_iter(data));
+        if pos < xml_end {
+            continue;
+        }
         let mut stopwatch = Stopwatch::new("parse", 0);
-        let r = kras().parse(data);
-        stopwatch.stop();
-        if let Ok(mut r) = r {
-            debug!("PARSED: {:?}", r);
-            if pos > start {
-                res.push(KrasValue::RawStr(String::from_iter(buf[start..pos].iter())));
+        // an xml element spans several detected chunks: <a>, </a>
+        if let Ok((r, e)) = xml_element().parse_at(&buf[..end], pos) {
+            found.push((pos, e - pos, r));
+            xml_end = e;
+            continue;
+        }
+        let prefix = data_prefix(&buf, pos);
+        let prefixed = if prefix < pos {
+            kras().parse(&buf[prefix..pos + data.len()]).ok().map(|r| (prefix, r))
+        }
+        else {
+            let prefix = record_prefix(&buf, pos);
+            let record = |prefix| {
+                (record() - pom::parser::end())
+                    .parse(&buf[prefix..pos + data.len()])
+                    .ok()
+            };
+            prefix.and_then(|prefix| record(prefix).map(|r| (prefix, r)))
+        };
+        let r = match prefixed {
+            Some((prefix, r)) => {
+                // the prefix may contain data that is already found: (1) of php array(1) { }
+                while found.last().is_some_and(|(p, _, _)| *p >= prefix) {
+                    found.pop();
+                }
+                Ok((prefix, r))
             }
-            start = pos + data.len();
-            let mut stopwatch = Stopwatch::new("postprocess", 0);
-            r.postprocess(sort);
-            if recursive {
-                let rec_parser = RecursiveStringParser(sort, robust);
-                r.visit(&rec_parser)
+            None => kras().parse(data).map(|r| (pos, r)),
+        };
+        stopwatch.stop();
+        match r {
+            Ok((start, r)) => found.push((start, pos + data.len() - start, r)),
+            Err(err) => {
+                // TODO what will happend here?
+                debug!("parse error {:?}", err);
             }
-            stopwatch.stop();
-            debug!("POSTPROC: {:?}", r);
-            res.push(r);
         }
-        else {
-            // TODO what will happend here?
-            debug!("parse error {:?}", r);
+    }
+    let recovered = recovered.map(|(pos, r)| (pos, buf.len() - pos, r));
+    for (pos, len, mut r) in found.into_iter().chain(recovered) {
+        debug!("PARSED: {:?}", r);
+        if pos > start {
+            res.extend(raw_parts(&buf[start..pos]));
         }
+        start = pos + len;
+        let mut stopwatch = Stopwatch::new("postprocess", 0);
+        r.postprocess(opts.sort);
+        stopwatch.stop();
+        debug!("POSTPROC: {:?}", r);
+        res.push(r);
     }
     if start < buf.len() {
-        res.push(KrasValue::RawStr(String::from_iter(buf[start..].iter())));
+        res.extend(raw_parts(&buf[start..]));
+    }
+    if opts.recursive || opts.decode {
+        let rec_parser = RecursiveStringParser(opts);
+        for r in res.iter_mut() {
+            r.visit(&rec_parser)
+        }
     }
     KrasValue::RawList(res)
 }
@@ -222,12 +1000,14 @@ pub fn parse_str(s: &str, sort: bool, recursive: bool, robust: bool) -> KrasValu
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::options::{OutputFormat, RenderOptions};
 
     fn check_single_value(input: &str, expected: &KrasValue) {
         check_single_value_with(input, expected, |a, b| a == b)
     }
 
     // fn check_single_value_with(input: &str, expected: &KrasValue, cmp_with: Fn(&KrasValue, &KrasValue) -> bool) { // ? this looks like the same thing but does not compiles
+    #[allow(clippy::get_first, clippy::needless_borrow, clippy::assertions_on_constants)]
     fn check_single_value_with<F>(input: &str, expected: &KrasValue, cmp_with: F)
     where
         F: Fn(&KrasValue, &KrasValue) -> bool,
@@ -243,6 +1023,18 @@ mod test {
         assert!(false, "{:?} != {:?}", res, expected);
     }
 
+    fn json(res: &KrasValue) -> String {
+        res.render(&RenderOptions::new().indent(0).format(OutputFormat::Json))
+    }
+
+    // the input has a single detected value and renders on one line as `rendered`
+    fn check_data(input: &str, rendered: &str) -> KrasValue {
+        let res = parse_str(input, &ParseOptions::new());
+        assert_eq!(res.data().len(), 1, "{input}");
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), rendered);
+        res
+    }
+
     #[test]
     fn test_parse_str() {
         let tests = vec![
@@ -254,7 +1046,7 @@ mod test {
                         vec![KrasValue::Pair((
                             Box::new(KrasValue::Ident("x".to_string())),
                             ":".to_string(),
-                            Box::new(KrasValue::Str(('"', "".to_string(), "".to_string()))),
+                            Box::new(KrasValue::Str(("\"".to_string(), "".to_string(), "".to_string()))),
                             None,
                         ))],
                         "}".to_string(),
@@ -263,12 +1055,461 @@ mod test {
             ),
         ];
         for (s, exp) in tests {
-            let res = parse_str(s, true, true, false);
+            let res = parse_str(s, &ParseOptions::new().sort(true).recursive(true));
             assert_eq!(res, exp);
         }
     }
 
     #[test]
+    fn test_data() {
+        let res = parse_str("a [1] b {} c", &ParseOptions::new());
+        let data = res.data();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].render(&RenderOptions::new()), "[1]");
+        assert_eq!(data[1].render(&RenderOptions::new()), "{}");
+        assert!(parse_str("a b c", &ParseOptions::new()).data().is_empty());
+    }
+
+    #[test]
+    fn test_comments() {
+        let tests = [
+            ("{/* c */ a: 1}", "{/* c */ a: 1}"),
+            ("[1, # c\n 2]", "[\n  1, \n  # c\n  2\n]"),
+            (
+                "{a: 1, // c\n b: /* x */ 2}",
+                "{\n  a: 1, \n  // c\n  /* x */ \n  b: 2\n}",
+            ),
+            (
+                "{url: http://example.com, b: 2 // c\n}",
+                "{\n  b: 2, \n  url: http://example.com\n  // c\n}",
+            ),
+            (
+                "{src: //cdn.example.com/x.js, n: [1]}",
+                "{n: [1], src: //cdn.example.com/x.js}",
+            ),
+        ];
+        for (input, expected) in tests {
+            let res = parse_str(input, &ParseOptions::new().sort(true));
+            assert_eq!(res.render(&RenderOptions::new()), expected, "{input}");
+        }
+        let res = parse_str("{b: 1, /* a */ a: 2}", &ParseOptions::new().sort(true));
+        assert_eq!(res.render(&RenderOptions::new()), "{/* a */ a: 2, b: 1}");
+    }
+
+    #[test]
+    fn test_go() {
+        let tests = [
+            ("map[a:1 b:2]", "map[a: 1 b: 2]"),
+            ("v=&{Name:foo Age:3}", "v=&{Name: foo Age: 3}"),
+            (
+                "{Name:foo Inner:{X:1 Y:[a b]} M:map[k:map[z:2]] P:<nil>}",
+                "{Name: foo Inner: {X: 1 Y: [a b]} M: map[k: map[z: 2]] P: <nil>}",
+            ),
+            ("bitmap[1 2]", "bitmap[1 2]"),
+            ("{a:1}", "{a: 1}"),
+            ("{url: http://example.com}", "{url: http://example.com}"),
+        ];
+        for (input, expected) in tests {
+            check_data(input, expected);
+        }
+        let res = parse_str("map[b:2 a:1 c:3]", &ParseOptions::new().sort(true));
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), "map[a: 1 b: 2 c: 3]");
+        let res = parse_str("&{A:1}", &ParseOptions::new());
+        assert!(matches!(res.data()[0], KrasValue::Constructor(_)));
+        // every level of nested braces is parsed once, not as go and then again as a list
+        let input = format!("x = {}1, 1{}}}", "{".repeat(24), "}, 1".repeat(23));
+        let res = parse_str(&input, &ParseOptions::new());
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), input);
+        let input = (0..24).fold("1".to_string(), |s, _| format!("{{a:{s},b:1}}"));
+        assert_eq!(parse_str(&input, &ParseOptions::new()).data().len(), 1);
+        let input = (0..24).fold("1".to_string(), |s, _| format!("{{A:{s} B:[x y]}}"));
+        let res = parse_str(&input, &ParseOptions::new());
+        assert_eq!(
+            res.select(&".A.A.A.B[1]".parse().unwrap()),
+            vec![&KrasValue::Ident("y".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_php() {
+        let tests = [
+            (
+                "got Array ( [a] => 1 [name] => John Smith [list] => Array ( [0] => x [1] => (y) z ) [e] =>  ) done",
+                "got Array(a => 1 name => John Smith list => Array(0 => x 1 => (y) z) e => ) done",
+            ),
+            ("stdClass Object ( [id] => 5 )", "stdClass(id => 5)"),
+            (
+                r#"array(3) { ["a"]=> int(1) ["b"]=> string(5) "a "b"" [0]=> array(1) { [0]=> float(1.5) } }"#,
+                r#"array{"a" => 1 "b" => "a \"b\"" 0 => array{0 => 1.5}}"#,
+            ),
+            (
+                r#"object(Foo)#1 (2) { ["x":protected]=> NULL ["y":"Foo":private]=> bool(true) }"#,
+                r#"Foo{"x" => NULL "y" => true}"#,
+            ),
+            (r#"array(1) { ["я"]=> string(2) "я" }"#, r#"array{"я" => "я"}"#),
+            ("array ( 'a' => 1 )", "array('a' => 1)"),
+            ("Array\n(\n    [a] => 1\n    [b] => \n)\n", "Array(a => 1 b => )\n"),
+        ];
+        for (input, expected) in tests {
+            check_data(input, expected);
+        }
+        let res = parse_str("Array ( [b] => x [a] => 2 )", &ParseOptions::new());
+        assert_eq!(res.select(&".a".parse().unwrap())[0].render(&RenderOptions::new()), "2");
+    }
+
+    #[test]
+    fn test_beam() {
+        let tests = [
+            (
+                r#"got #{<<"id">> => 1, name => <<"bob">>, tags => [a, b]} ok"#,
+                r#"got {"id": 1, "name": "bob", "tags": ["a", "b"]} ok"#,
+            ),
+            (
+                r#"{ok, [{a, 1}, {b, "str"}, {c, 'charlist'}]}"#,
+                r#"["ok", [["a", 1], ["b", "str"], ["c", "charlist"]]]"#,
+            ),
+            (
+                r#"%User{name: "x", role: :admin, meta: %{"k" => :"quoted atom"}, bin: <<1, 2, 3>>}"#,
+                r#"{"%User": {"name": "x", "role": "admin", "meta": {"k": "quoted atom"}, "bin": [1, 2, 3]}}"#,
+            ),
+            ("[:ok, :error?, %{}, #{}, <<>>]", r#"["ok", "error?", {}, {}, []]"#),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(json(&check_data(input, input)), expected);
+        }
+        // maps stay maps: a value followed by ':' is not an elixir atom
+        let input = r#"#{a => #{b => 1}, <<"c">> => [1]} %{d: :e} {"f":"g","h":{"i":"j"}}"#;
+        let res = parse_str(input, &ParseOptions::new());
+        assert_eq!(
+            json(&res),
+            r#"{"a": {"b": 1}, "c": [1]} {"d": "e"} {"f": "g", "h": {"i": "j"}}"#
+        );
+        let res = parse_str("[:ok, :\"a b\"]", &ParseOptions::new());
+        let atoms = res.select(&".[*]".parse().unwrap());
+        assert_eq!(
+            atoms,
+            vec![
+                &KrasValue::Atom(":ok".to_string()),
+                &KrasValue::Atom(":\"a b\"".to_string())
+            ]
+        );
+        assert_eq!(atoms[1].atom_name(), Some("a b".to_string()));
+    }
+
+    #[test]
+    fn test_edn() {
+        let tests = [
+            (
+                r#"{:a 1, :user/name "x" :tags #{:a :b} :at #inst "2020-01-01T00:00:00Z" :id #uuid "f81d4fae"}"#,
+                r#"{"a": 1, "user/name": "x", "tags": ["a", "b"], "at": "2020-01-01T00:00:00Z", "id": "f81d4fae"}"#,
+            ),
+            ("(defn inc [x] (+ x 1))", r#"["defn", "inc", ["x"], ["+", "x", 1]]"#),
+            (
+                "{::local [1 nil] :valid? true}",
+                r#"{":local": [1, null], "valid?": true}"#,
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(json(&check_data(input, input)), expected);
+        }
+        // keyword keys become pairs, sets and vectors stay lists
+        let res = parse_str("{:b [1 2] :a #{:x :y}}", &ParseOptions::new().sort(true));
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), "{:a #{:x :y} :b [1 2]}");
+        assert_eq!(res.select(&".b[1]".parse().unwrap()).len(), 1);
+        assert_eq!(res.select(&".a[*]".parse().unwrap()).len(), 2);
+        // not every list in braces is a map
+        let res = parse_str("{John 30}", &ParseOptions::new());
+        assert_eq!(res.select(&".[*]".parse().unwrap()).len(), 2);
+    }
+
+    #[test]
+    fn test_lua_ruby() {
+        let tests = [
+            (r#"t={["key"]=1, x={[1]="a"}, }"#, r#"t={"key": 1, "x": {"1": "a"}}"#),
+            (
+                r#"{:sym => 1, key: "v", "s" => :x, nil => nil}"#,
+                r#"{"sym": 1, "key": "v", "s": "x", "nil": null}"#,
+            ),
+            (
+                r#"user=#<User id: 1, name: "x", address: #<Address city: "y">> ok"#,
+                r#"user={"User": {"id": 1, "name": "x", "address": {"Address": {"city": "y"}}}} ok"#,
+            ),
+            (
+                r#"[#<User:0x000055d5 @id=1, @name="x">, #<Object:0x0001>, #<Set: {1, 2}>]"#,
+                r#"[{"User:0x000055d5": {"@id": 1, "@name": "x"}}, ["Object:0x0001"], ["Set:", [1, 2]]]"#,
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(json(&check_data(input, input)), expected);
+        }
+        let res = parse_str(r#"{["key"]=1, [2]="b"}"#, &ParseOptions::new());
+        assert_eq!(
+            res.select(&".key".parse().unwrap()),
+            vec![&KrasValue::Num(OrdF64(1.0, "1".to_string()))]
+        );
+        let res = parse_str("#<User id: 1, name: \"x\">", &ParseOptions::new());
+        assert_eq!(
+            res.render(&RenderOptions::new().indent(2).width(12)),
+            "#<User\n  id: 1, \n  name: \"x\"\n>"
+        );
+    }
+
+    #[test]
+    fn test_java() {
+        let tests = [
+            (
+                "got User(id=1, name=Bob Smith, tags=[a, b], url=http://x.com/a?b=1, at=2020-01-01T10:00:00Z) ok",
+                r#"got {"User": {"id": 1, "name": "Bob Smith", "tags": ["a", "b"], "url": "http://x.com/a?b=1", "at": "2020-01-01T10:00:00Z"}} ok"#,
+            ),
+            (
+                "User{id=1, name='Bob', email=null}",
+                r#"{"User": {"id": 1, "name": "Bob", "email": null}}"#,
+            ),
+            (
+                "Order(id=5, user=User(id=1, name=Bob), items=[Item(sku=A-1, qty=2)], note=)",
+                r#"{"Order": {"id": 5, "user": {"User": {"id": 1, "name": "Bob"}}, "items": [{"Item": {"sku": "A-1", "qty": 2}}], "note": ""}}"#,
+            ),
+            ("Point[x=1, y=2]", r#"{"Point": {"x": 1, "y": 2}}"#),
+            (
+                "Config{path=/usr/local/bin, mode=fast mode}",
+                r#"{"Config": {"path": "/usr/local/bin", "mode": "fast mode"}}"#,
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(json(&check_data(input, input)), expected);
+        }
+        let res = parse_str("User(id=1, name=Bob Smith, at=12:30:00)", &ParseOptions::new());
+        let name = res.select(&".name".parse().unwrap());
+        assert_eq!(name, vec![&KrasValue::Ident("Bob Smith".to_string())]);
+        assert_eq!(
+            res.select(&".at".parse().unwrap()),
+            vec![&KrasValue::Ident("12:30:00".to_string())]
+        );
+        // the name of a call with positional args is not a part of the data
+        let res = parse_str("Math.max(1, 2)", &ParseOptions::new());
+        assert_eq!(res.data()[0].render(&RenderOptions::new()), "(1, 2)");
+    }
+
+    #[test]
+    fn test_xml() {
+        let tests = [
+            (
+                r#"req: <soap:Envelope xmlns:soap="http://x/"><soap:Body><m:Get id='1'/></soap:Body></soap:Envelope> ok"#,
+                r#"req: {"soap:Envelope": {"@xmlns:soap": "http://x/", "soap:Body": {"m:Get": {"@id": "1"}}}} ok"#,
+            ),
+            (
+                "<p>Hello <b>world</b>!<br>x &amp; y</p>",
+                r##"{"p": {"b": "world", "br": null, "#text": "Hello ! x & y"}}"##,
+            ),
+            ("<a><!-- c --><![CDATA[1 < 2]]></a>", r#"{"a": "1 < 2"}"#),
+            ("[<a>1</a>, 2]", r#"[{"a": "1"}, 2]"#),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(json(&check_data(input, input)), expected);
+        }
+        let res = parse_str("<a x=\"1\">\n  <b>text</b>\n  <c/>\n</a>", &ParseOptions::new());
+        assert_eq!(
+            res.render(&RenderOptions::new().width(20)),
+            "<a x=\"1\">\n  <b>text</b>\n  <c/>\n</a>"
+        );
+        assert_eq!(res.render(&RenderOptions::new()), "<a x=\"1\"><b>text</b><c/></a>");
+        // not an element: the end tag is missing or does not match
+        for input in ["<class 'str'>", "<b>bold", "<a>1</b>"] {
+            let res = parse_str(input, &ParseOptions::new());
+            assert!(
+                res.data().iter().all(|x| !matches!(x, KrasValue::Element(_))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_python() {
+        let tests = [
+            (
+                "at=datetime.datetime(2024, 1, 2, 3, 4, tzinfo=datetime.timezone.utc) ok",
+                r#"at="2024-01-02T03:04:00+00:00" ok"#,
+            ),
+            ("Decimal('1.20')", "1.20"),
+            ("x = OrderedDict([('a', 1), ('b', 2)])", r#"x = {"a": 1, "b": 2}"#),
+            ("defaultdict(<class 'list'>, {'a': [1]})", r#"{"a": [1]}"#),
+        ];
+        for (input, expected) in tests {
+            let res = check_data(input, input);
+            assert!(matches!(res.data()[0], KrasValue::Constructor(_)), "{input}");
+            assert_eq!(json(&res), expected);
+        }
+    }
+
+    #[test]
+    fn test_number_variants() {
+        let input = "[0b1010, 0o755, 1_000_000, 5u32, -1.0f64, 10L, +5, .5, NaN, Infinity, -inf, 0xFF_FF]";
+        let res = parse_str(input, &ParseOptions::new());
+        assert_eq!(res.data().len(), 1);
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), input);
+        let items = match &res.data()[0] {
+            KrasValue::List((_, items, _)) => items,
+            v => panic!("not a list: {v:?}"),
+        };
+        let nums = items
+            .iter()
+            .filter_map(|v| match v {
+                KrasValue::ListItem((v, _)) => match v.as_ref() {
+                    KrasValue::Num(OrdF64(f, _)) => Some(*f),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(nums.len(), 12);
+        assert_eq!(&nums[..8], [10.0, 493.0, 1e6, 5.0, -1.0, 10.0, 5.0, 0.5]);
+        assert!(nums[8].is_nan());
+        assert_eq!(&nums[9..], [f64::INFINITY, f64::NEG_INFINITY, 65535.0]);
+        let mut sorted = items
+            .iter()
+            .filter_map(|v| match v {
+                KrasValue::ListItem((v, _)) => match v.as_ref() {
+                    KrasValue::Num(n) => Some(n.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        sorted.sort();
+        let sorted = sorted.iter().map(|OrdF64(_, r)| r.as_str()).collect::<Vec<_>>();
+        let expected = [
+            "-inf",
+            "-1.0f64",
+            ".5",
+            "5u32",
+            "+5",
+            "0b1010",
+            "10L",
+            "0o755",
+            "0xFF_FF",
+            "1_000_000",
+            "Infinity",
+            "NaN",
+        ];
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn test_logfmt() {
+        let tests = [
+            (r#"level=info user="bob \"b\"" took=12ms n=5"#, 1),
+            ("ts=2024-01-02T03:04:05Z msg=done [1] a=1 b=-2.5", 3),
+            ("path=/a?b=c x=1 end", 1),
+            ("single a=1 and x;a=1 b=2", 0),
+            ("a=1  b=2", 0),
+        ];
+        for (input, count) in tests {
+            let res = parse_str(input, &ParseOptions::new());
+            assert_eq!(res.data().len(), count, "{input}");
+            assert_eq!(res.render(&RenderOptions::new().width(20)), input);
+        }
+        let res = parse_str(r#"level=info user="bob" n=5"#, &ParseOptions::new());
+        assert_eq!(
+            res.select(&".n".parse().unwrap()),
+            vec![&KrasValue::Num(OrdF64(5.0, "5".to_string()))]
+        );
+        let res = parse_str("retention=30d ttl=7d", &ParseOptions::new());
+        assert_eq!(
+            res.select(&".[*]".parse().unwrap()),
+            vec![
+                &KrasValue::Ident("30d".to_string()),
+                &KrasValue::Ident("7d".to_string())
+            ]
+        );
+        let res = parse_str(r#"a=1 data="{\"b\": [2]}""#, &ParseOptions::new().recursive(true));
+        assert_eq!(res.select(&".data.b[0]".parse().unwrap()).len(), 1);
+    }
+
+    #[test]
+    fn test_decode() {
+        let tests = [
+            ("GET /?q=%7B%22a%22%3A%20%5B1%5D%7D ok", r#"GET /?q=#url {"a": [1]} ok"#),
+            (
+                r#"{"p": "eyJhIjogMX0=", "h": "5b312c20325d"}"#,
+                r#"{"p": #base64 {"a": 1}, "h": #hex [1, 2]}"#,
+            ),
+            ("id 12345678 abcdefgh", "id 12345678 abcdefgh"),
+        ];
+        let opts = ParseOptions::new().decode(true);
+        for (input, expected) in tests {
+            assert_eq!(parse_str(input, &opts).render(&RenderOptions::new()), expected);
+            assert_eq!(
+                parse_str(input, &ParseOptions::new()).render(&RenderOptions::new()),
+                input
+            );
+        }
+        let res = parse_str("q=%7B%22a%22%3A%20%5B1%5D%7D", &opts);
+        assert_eq!(
+            res.select(&".a[0]".parse().unwrap()),
+            vec![&KrasValue::Num(OrdF64(1.0, "1".to_string()))]
+        );
+        let json = res.render(&RenderOptions::new().format(OutputFormat::Json));
+        assert_eq!(json, r#"q={"a": [1]}"#);
+    }
+
+    #[test]
+    fn test_unescape() {
+        let input = r#"{"trace": "Error: x\n  at f (a.js:1)", "n": 1, "s": "a\tb", "l": ["a\nb", 2]}"#;
+        let res = parse_str(input, &ParseOptions::new());
+        let expected = [
+            "{",
+            r#"  "trace": |"#,
+            "    Error: x",
+            "      at f (a.js:1)",
+            r#"  "n": 1, "#,
+            r#"  "s": "a\tb", "#,
+            r#"  "l": ["#,
+            "    |",
+            "      a",
+            "      b",
+            "    2",
+            "  ]",
+            "}",
+        ];
+        assert_eq!(res.render(&RenderOptions::new().unescape(true)), expected.join("\n"));
+        assert_eq!(res.render(&RenderOptions::new().indent(0)), input);
+        assert_eq!(res.render(&RenderOptions::new().indent(0).unescape(true)), input);
+        let json = res.render(&RenderOptions::new().indent(0).unescape(true).format(OutputFormat::Json));
+        assert_eq!(json, input);
+    }
+
+    #[test]
+    fn test_recover() {
+        let tests = [
+            (r#"got {"a": [1, 2, {"b": "#, r#"got {"a": [1, 2, {"b": …}]}"#),
+            (r#"got {"a": [1, 2"#, r#"got {"a": [1, 2…]}"#),
+            (r#"got {"a": "abc"#, r#"got {"a": "abc"…}"#),
+            (r#"{"a": "abc\"#, r#"{"a": "abc"…}"#),
+            ("{'a': '''it's", "{'a': '''it's'''…}"),
+            ("{a: `x ${ {b: 1", "{a: `x ${ {b: 1}}`…}"),
+            (r#"[1] {"a": 1, "b": [2, 3], "#, r#"[1] {"a": 1, "b": [2, 3], …}"#),
+            ("(1, [2, ", "(1, [2, …])"),
+            (r#"smile :( got {"a": 1"#, r#"smile :( got {"a": 1…}"#),
+            ("f(x) [a, (b", "f(x) [a, (b…)]"),
+            ("a < b {'c': 1", "a < b {'c': 1…}"),
+            ("{a: 1} [2]", "{a: 1} [2]"),
+            ("{a: 1]", "{a: 1]"),
+            ("[User(id=1, name=Bo", "[User(id=1, name=Bo…)]"),
+            // U+E000 is a char of the input, not a mark
+            ("[\"\u{E000}\", 2", "[\"\u{E000}\", 2…]"),
+            ("x \u{E000} {\"a\": [1", "x \u{E000} {\"a\": [1…]}"),
+        ];
+        for (input, expected) in tests {
+            let res = parse_str(input, &ParseOptions::new().recover(true));
+            assert_eq!(res.render(&RenderOptions::new()), expected, "{input}");
+        }
+        let res = parse_str(r#"{"a": 1, "b": [2"#, &ParseOptions::new().recover(true));
+        assert_eq!(res.data().len(), 1);
+        assert!(parse_str(r#"{"a": [1"#, &ParseOptions::new()).data().is_empty());
+    }
+
+    #[test]
+    #[allow(clippy::unused_unit)]
     fn test_kras() -> () {
         let tests = vec![
             ("{}", KrasValue::List(("{".to_string(), vec![], "}".to_string()))),
@@ -283,14 +1524,20 @@ mod test {
                     "}".to_string(),
                 )),
             ),
-            (r#"b''"#, KrasValue::Str(('\'', "b".to_string(), "".to_string()))),
+            (
+                r#"b''"#,
+                KrasValue::Str(("'".to_string(), "b".to_string(), "".to_string())),
+            ),
             (
                 "{a=>b''}",
                 KrasValue::List((
                     "{".to_string(),
                     vec![
                         KrasValue::ListItem((Box::new(KrasValue::Ident("a".to_string())), Some("=>".to_string()))),
-                        KrasValue::ListItem((Box::new(KrasValue::Str(('\'', "b".to_string(), "".to_string()))), None)),
+                        KrasValue::ListItem((
+                            Box::new(KrasValue::Str(("'".to_string(), "b".to_string(), "".to_string()))),
+                            None,
+                        )),
                     ],
                     "}".to_string(),
                 )),
@@ -306,18 +1553,87 @@ mod test {
     #[test]
     fn test_unicode() {
         let tests = vec![
-            (r#"["\u044f"]"#, KrasValue::Str(('"', "".to_string(), "я".to_string()))),
+            (
+                r#"["\u044f"]"#,
+                KrasValue::Str(("\"".to_string(), "".to_string(), r"\u044f".to_string())),
+            ),
             (
                 r#"["\u044f2"]"#,
-                KrasValue::Str(('"', "".to_string(), "я2".to_string())),
+                KrasValue::Str(("\"".to_string(), "".to_string(), r"\u044f2".to_string())),
             ),
         ];
         for (input, expected) in tests {
-            check_single_value(&input, &expected);
+            check_single_value(input, &expected);
+        }
+        // the body is kept as written, str_value decodes the escapes
+        let tests = [
+            (r#"["\u044f"]"#, "я"),
+            (r#"["\u044f2"]"#, "я2"),
+            (r#"['\x41\n\q']"#, "A\n\\q"),
+            (r#"['\u{1F600}']"#, "😀"),
+        ];
+        for (input, expected) in tests {
+            let res = parse_str(input, &ParseOptions::new());
+            let values = res.select(&".[0]".parse().unwrap());
+            assert_eq!(values[0].str_value(), Some(expected.to_string()), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        // strings are printed exactly as they were written
+        let tests = [
+            r#"["a\"b", 'c\'d', "\u044f\n"]"#,
+            r#"{'re': r'\d+\.\w', "path": R"C:\dir\\"}"#,
+            r#"["""a "quoted" 'text'""", '''it's''', '''''', "", '']"#,
+            "['''multi\nline''', \"\"\"a\\\"\"\"\"]",
+            r#"[f'{x!r}: {y:>10}', rb"\x00", F"{{}}"]"#,
+            r##"Foo {re: r#"a "b" \d"#, raw: br"\x", empty: r#""#, bytes: b'\x00', c: 'a', e: '\u{1F600}'}"##,
+            r#"{msg: `user ${user.name} has ${ {a: "}"}.a } items`, tag: html`<b>${`nested ${x}`}</b>`, e: `\``}"#,
+        ];
+        for input in tests {
+            check_data(input, input);
         }
+        let res = parse_str(
+            r###"[r'\d\'', '\d\'', """a\tb""", r'''\t''', r##"a"#b"##, '\0']"###,
+            &ParseOptions::new(),
+        );
+        let values = res
+            .select(&".[*]".parse().unwrap())
+            .iter()
+            .map(|v| v.str_value().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![r"\d\'", r"\d'", "a\tb", r"\t", r##"a"#b"##, "\0"]);
+    }
+
+    #[test]
+    fn test_big_numbers() {
+        let res = parse_str(
+            "[9007199254740993, 9007199254740992, 0xffffffffffffffffff, 0.10000000000000000001, 0.1]",
+            &ParseOptions::new(),
+        );
+        let mut nums = res.select(&"..[*]".parse().unwrap());
+        assert_eq!(nums.len(), 5);
+        nums.sort();
+        let nums = nums.iter().map(|n| n.render(&RenderOptions::new())).collect::<Vec<_>>();
+        assert_eq!(
+            nums,
+            vec![
+                "0.1",
+                "0.10000000000000000001",
+                "9007199254740992",
+                "9007199254740993",
+                "0xffffffffffffffffff"
+            ]
+        );
+        assert_ne!(
+            KrasValue::Num(OrdF64(9007199254740993.0, "9007199254740993".to_string())),
+            KrasValue::Num(OrdF64(9007199254740992.0, "9007199254740992".to_string())),
+        );
     }
 
     #[test]
+    #[allow(clippy::needless_borrow, clippy::assertions_on_constants, clippy::iter_skip_next)]
     fn test_numbers() {
         let tests = vec![
             ("[1]", KrasValue::Num(OrdF64(1.0, "1".to_string()))),
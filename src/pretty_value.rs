@@ -6,12 +6,22 @@ use pretty::{
 };
 use termcolor::ColorChoice;
 
+use crate::{
+    number::Decimal,
+    options::{OutputFormat, RenderOptions},
+    parse::unescape,
+};
+
+/// A parsed number: the approximate value and the original text used for rendering
+///
+/// Numbers are compared by the exact value of their text, so integers and decimals that do not fit
+/// into f64 are still compared and sorted correctly
 #[derive(Debug, Clone)]
 pub struct OrdF64(pub f64, pub String);
 
 impl PartialEq for OrdF64 {
     fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -19,47 +29,85 @@ impl Eq for OrdF64 {}
 
 impl Ord for OrdF64 {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+        let (a, b) = (Decimal::parse(&self.1), Decimal::parse(&other.1));
+        // numbers without an exact value have a fixed place: -inf goes first, inf after all the exact values,
+        // NaN last
+        let rank = |n: f64, exact: &Option<Decimal>| match exact {
+            Some(_) => 1,
+            None if n == f64::NEG_INFINITY => 0,
+            None if n == f64::INFINITY => 2,
+            None => 3,
+        };
+        rank(self.0, &a).cmp(&rank(other.0, &b)).then_with(|| match (a, b) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => self.0.total_cmp(&other.0),
+        })
     }
 }
 
 impl PartialOrd for OrdF64 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
+/// A node of the parsed data tree
 #[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd)]
+#[non_exhaustive]
 pub enum KrasValue {
-    // raw string; the leftovers of parse(); should be printed without highlighting
+    /// raw string; the leftovers of parse(); should be printed without highlighting
     RawStr(String),
 
-    // list that don't have braces or delimeters; just a storage for raw strings and real (parsed) values
+    /// list that don't have braces or delimeters; just a storage for raw strings and real (parsed) values
     RawList(Vec<KrasValue>),
 
-    // quoted string: (quote, prefix, str): b"foo" = ('"', "b", "foo")
-    Str((char, String, String)),
+    /// quoted string: (quote, prefix, body as written): b"foo\n" = ("\"", "b", "foo\\n"), """foo""" =
+    /// ("\"\"\"", "", "foo"), r#"foo"# = ("#\"", "r", "foo"). The closing quote is the opening one
+    /// reversed. See [`KrasValue::str_value`] for the value with escapes decoded
+    Str((String, String, String)),
 
-    // value, delim?
+    /// value, delim?
     ListItem((Box<KrasValue>, Option<String>)),
 
-    // value, delim, value, delim2?
+    /// value, delim, value, delim2?
     Pair((Box<KrasValue>, String, Box<KrasValue>, Option<String>)),
 
-    // open brace, ListItem | Pair, close
+    /// open brace, ListItem | Pair, close. Logfmt pairs have no braces: a=1 b=2
     List((String, Vec<KrasValue>, String)),
 
-    // a literal identificator, including null, true, false and any var name
+    /// a literal identificator, including null, true, false and any var name
     Ident(String),
 
-    // ident, list
+    /// an atom, symbol or keyword with the leading colon: `:ok`, `:"quoted atom"`
+    Atom(String),
+
+    /// ident, list; or tag, value for edn tagged literals: #inst "2020-01-01"
     Constructor((Box<KrasValue>, Box<KrasValue>)),
 
-    // a number
+    /// a number
     Num(OrdF64),
+
+    /// a comment inside of a list, including the comment marks: "// foo", "/* foo */", "# foo"
+    Comment(String),
+
+    /// an xml or html element: (tag, attributes, the end of the start tag, children). Attributes are
+    /// [`KrasValue::Pair`]s, the start tag ends with `>` or `/>`. Children are elements,
+    /// [`KrasValue::Text`] and [`KrasValue::Comment`]; None if there is no end tag: `<br/>`, `<br>`
+    Element((String, Vec<KrasValue>, String, Option<Vec<KrasValue>>)),
+
+    /// text of an xml element as written, including cdata: `a &amp; b`, `<![CDATA[a & b]]>`
+    Text(String),
+
+    /// data decoded from an encoded payload: (encoding, value). The encoding is `url`, `hex` or `base64`,
+    /// the value is the parsed decoded text. See [`ParseOptions::decode`](crate::ParseOptions::decode)
+    Decoded((String, Box<KrasValue>)),
+
+    /// the point where truncated data was cut. See [`ParseOptions::recover`](crate::ParseOptions::recover)
+    Truncated,
 }
 
 impl KrasValue {
+    /// Convert lists that look like dicts into lists of [`KrasValue::Pair`], optionally sorting them
     pub fn postprocess(&mut self, sort: bool) {
         // convert lists to dicts, sort
         match self {
@@ -68,44 +116,68 @@ impl KrasValue {
                 // to Pairs
                 // {key1: val1, key2: val2} => [ (key1, :), (val1, ,), (key2, :), (val2, ())] => [ (key1, :, val1, ,), (key2, :, val2, ()) ]
                 let mut is_dict = true;
-                let len = items.len();
+                // comments are skipped: they are neither keys nor values
+                // so is the truncation mark that follows the last value of truncated data
+                let last = items
+                    .iter()
+                    .rposition(|item| !item.is_comment() && !item.is_truncated());
+                let mut n = 0;
                 for (i, item) in items.iter_mut().enumerate() {
                     item.postprocess(sort);
+                    if n % 2 == 0 && item.is_truncated() {
+                        continue;
+                    }
                     if let KrasValue::ListItem((_, ref mut d)) = item {
                         // fix lisp-style arrays (add space)
                         // (foo bar) => parse => (foo<none> bar<none>) => fix
                         // => (foo<space> bar<none>)
-                        if i < len - 1 && d.is_none() {
+                        if last.is_some_and(|last| i < last) && d.is_none() {
                             *d = Some(" ".to_string())
                         }
-                        if i % 2 == 0 {
+                        if n % 2 == 0 {
                             // each even list item delimeter must be a dict separator
                             is_dict = match d {
                                 Some(d) => d == "=>" || d == ":" || d == "=",
                                 None => false,
                             };
                         }
+                        n += 1;
                     }
                 }
+                if !is_dict && o == "{" {
+                    is_dict = is_edn_map(items);
+                }
                 if is_dict {
                     // TODO can it be done without clone?
-                    let mut res = Vec::new();
-                    for kv in items.chunks_mut(2) {
-                        if let [k, v] = kv {
-                            if let KrasValue::ListItem(ref mut k) = k {
-                                if let KrasValue::ListItem(ref mut v) = v {
-                                    res.push(KrasValue::Pair((
-                                        k.0.clone(),
-                                        k.1.clone().unwrap(),
-                                        v.0.clone(),
-                                        v.1.clone(),
-                                    )))
-                                }
+                    // comments are attached to the pair that follows them, so they stay in place after sort
+                    let mut pairs = Vec::new();
+                    let mut comments = Vec::new();
+                    let mut key = None;
+                    for item in items.iter() {
+                        match item {
+                            KrasValue::ListItem(_) if key.is_none() && item.is_truncated() => {
+                                comments.push(item.clone())
                             }
+                            KrasValue::ListItem(v) => match key.take() {
+                                None => key = Some(v),
+                                Some(k) => pairs.push((
+                                    std::mem::take(&mut comments),
+                                    KrasValue::Pair((k.0.clone(), k.1.clone().unwrap(), v.0.clone(), v.1.clone())),
+                                )),
+                            },
+                            _ => comments.push(item.clone()),
                         }
                     }
                     if sort {
-                        res.sort();
+                        pairs.sort_by(|a, b| a.1.cmp(&b.1));
+                    }
+                    let mut res = Vec::new();
+                    for (c, pair) in pairs {
+                        res.extend(c);
+                        res.push(pair);
+                    }
+                    res.extend(comments);
+                    if sort {
                         self.fix_comma(&mut res);
                     }
                     *self = KrasValue::List((o.to_string(), res, c.to_string()));
@@ -121,20 +193,28 @@ impl KrasValue {
         }
     }
 
-    fn fix_comma(&self, list: &mut Vec<KrasValue>) {
+    fn fix_comma(&self, list: &mut [KrasValue]) {
         // {"2": 2, "1": 1} => sort => {"1": 1<no comma> "2": 2,<extra comma>}
         // => fix => {"1": 1,<add comma> "2": 2<remove comma> } => {"1": 1, "2": 2}
+        // the delimiter of the list is kept: go maps are separated by spaces
 
-        let len = list.len();
+        let last = list.iter().rposition(|item| !item.is_comment());
+        let delim = list
+            .iter()
+            .find_map(|item| match item {
+                KrasValue::Pair((_, _, _, Some(d))) => Some(d.to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| ",".to_string());
         for (i, item) in list.iter_mut().enumerate() {
             if let KrasValue::Pair((k, d, v, d2)) = item {
-                match (d2.is_some(), i == len - 1) {
+                match (d2.is_some(), Some(i) == last) {
                     (true, true) => {
                         let d2 = None;
                         *item = KrasValue::Pair((k.clone(), d.to_string(), v.clone(), d2))
                     }
                     (false, false) => {
-                        let d2 = Some(",".to_string());
+                        let d2 = Some(delim.clone());
                         *item = KrasValue::Pair((k.clone(), d.to_string(), v.clone(), d2))
                     }
                     _ => {}
@@ -144,11 +224,95 @@ impl KrasValue {
     }
 }
 
+// clojure / edn maps have no kv delimiter: {:a 1 :b 2}. A list in braces with an even number of
+// items is a map if all of its keys are keywords. Commas are whitespace: {:a 1, :b 2}
+fn is_edn_map(items: &[KrasValue]) -> bool {
+    let items = items.iter().filter(|item| !item.is_comment()).collect::<Vec<_>>();
+    let is_key = |item: &&KrasValue| match item {
+        _ if item.is_truncated() => true,
+        KrasValue::ListItem((k, Some(d))) => d == " " && matches!(k.as_ref(), KrasValue::Atom(_)),
+        _ => false,
+    };
+    let even = items.len() % 2 == 0 || items.last().is_some_and(|item| item.is_truncated());
+    items.len() > 1 && even && items.iter().step_by(2).all(is_key)
+}
+
+impl KrasValue {
+    pub fn is_comment(&self) -> bool {
+        matches!(self, KrasValue::Comment(_))
+    }
+
+    /// Is this the mark of the point where truncated data was cut
+    pub fn is_truncated(&self) -> bool {
+        match self {
+            KrasValue::Truncated => true,
+            KrasValue::ListItem((v, _)) => v.is_truncated(),
+            _ => false,
+        }
+    }
+
+    fn is_line_comment(&self) -> bool {
+        match self {
+            KrasValue::Comment(s) => !s.starts_with("/*") && !s.starts_with("<!--"),
+            _ => false,
+        }
+    }
+
+    // the tag of an edn tagged literal: #inst, #uuid
+    pub(crate) fn is_tag(&self) -> bool {
+        matches!(self, KrasValue::Ident(s) if s.starts_with('#'))
+    }
+
+    /// The value of a quoted string with escape sequences decoded. Raw strings are returned as is
+    pub fn str_value(&self) -> Option<String> {
+        match self {
+            KrasValue::Str((_, p, s)) => Some(unescape(p, s)),
+            _ => None,
+        }
+    }
+
+    // the name of an atom without the colon and quotes
+    pub(crate) fn atom_name(&self) -> Option<String> {
+        match self {
+            KrasValue::Atom(s) => {
+                let name = s.strip_prefix(':').unwrap_or(s);
+                match name.strip_prefix('"').and_then(|n| n.strip_suffix('"')) {
+                    Some(quoted) => Some(unescape("", quoted)),
+                    None => Some(name.to_string()),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // the key of a lua table or a js computed property in brackets: { ["key"] = 1, [2] = "b" }
+    pub(crate) fn bracketed_key(&self) -> Option<&KrasValue> {
+        match self {
+            KrasValue::List((o, items, _)) if o == "[" && items.len() == 1 => match &items[0] {
+                KrasValue::ListItem((k, None)) => Some(k),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Detected values without the raw text around them
+    pub fn data(&self) -> Vec<&KrasValue> {
+        match self {
+            KrasValue::RawList(items) => items.iter().flat_map(|item| item.data()).collect(),
+            KrasValue::RawStr(_) => vec![],
+            _ => vec![self],
+        }
+    }
+}
+
+/// A callback that is applied to the values of a parsed tree by [`KrasValue::visit`]
 pub trait KrasVisitor {
     fn visit_str(&self, val: &mut KrasValue);
 }
 
 impl KrasValue {
+    /// Walk the tree and call `visitor` on every string value
     pub fn visit(&mut self, visitor: &dyn KrasVisitor) {
         match self {
             KrasValue::Str(_) => visitor.visit_str(self),
@@ -162,14 +326,24 @@ impl KrasValue {
                 }
             }
             KrasValue::Ident(_) => {}
+            KrasValue::Atom(_) => {}
             KrasValue::Constructor((_, args)) => args.visit(visitor),
             KrasValue::Num(_) => {}
+            KrasValue::Element((_, attrs, _, children)) => {
+                for x in attrs.iter_mut().chain(children.iter_mut().flatten()) {
+                    x.visit(visitor)
+                }
+            }
+            KrasValue::Text(_) => {}
+            KrasValue::Decoded((_, v)) => v.visit(visitor),
+            KrasValue::Comment(_) => {}
+            KrasValue::Truncated => {}
         }
     }
 }
 
 impl KrasValue {
-    fn kv_spaces(&self, d: String) -> RcDoc<ColorSpec> {
+    fn kv_spaces(&self, d: String) -> RcDoc<'_, ColorSpec> {
         // '=>' - spaces around
         // ':' - spaces to the right ': '
         // '=' - no spaces
@@ -187,68 +361,226 @@ impl KrasValue {
         }
     }
 
-    pub fn to_doc(&self, indent: usize, is_key: bool) -> RcDoc<ColorSpec> {
-        let nest = indent as isize; // why tf _i_size?
-        match self {
-            KrasValue::Str((q, p, s)) => RcDoc::as_string(p.to_string() + &q.to_string() + s + &q.to_string())
-                .annotate(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(is_key).clone()),
-            KrasValue::Ident(s) => {
-                RcDoc::as_string(s).annotate(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(is_key).clone())
+    // `space`: the first item is separated from the open brace by a space, like in #<User id: 1>
+    fn list_doc<'a>(
+        open: RcDoc<'a, ColorSpec>,
+        it: &'a [KrasValue],
+        cl: &'a str,
+        opts: &RenderOptions,
+        space: bool,
+    ) -> RcDoc<'a, ColorSpec> {
+        let nest = opts.indent as isize;
+        // nothing may follow a line comment on the same line, so the list must be broken
+        let has_line_comment = it.iter().any(|x| x.is_line_comment());
+        let line = || {
+            if has_line_comment {
+                RcDoc::hardline()
+            }
+            else {
+                RcDoc::line_()
             }
-            KrasValue::List((op, it, cl)) => RcDoc::text(op)
-                .annotate(ColorSpec::new().set_bold(true).clone())
+        };
+        let first = if space && !has_line_comment {
+            RcDoc::line()
+        }
+        else {
+            line()
+        };
+        open.append(
+            RcDoc::nil()
+                .append(first)
+                .nest(nest)
                 .append(
-                    RcDoc::nil()
-                        .append(RcDoc::line_())
+                    RcDoc::intersperse(it.iter().map(|x| x.to_doc(opts, false)), line())
                         .nest(nest)
+                        .append(line()),
+                )
+                .group(),
+        )
+        .append(
+            RcDoc::nil()
+                .append(cl)
+                .annotate(ColorSpec::new().set_bold(true).clone()),
+        )
+    }
+
+    // children of an xml element are indented only if there is no text between them. Breaking a line
+    // inside of a text would change it: <p>a <b>b</b>!</p>. The whitespace around text is collapsed to a space
+    fn xml_children_doc<'a>(children: &'a [KrasValue], opts: &RenderOptions) -> RcDoc<'a, ColorSpec> {
+        let text = |x: &KrasValue, f: fn(&str) -> bool| matches!(x, KrasValue::Text(s) if f(s));
+        let starts_with_space = |s: &str| s.starts_with(char::is_whitespace);
+        let ends_with_space = |s: &str| s.ends_with(char::is_whitespace);
+        let is_mixed = children.iter().any(|x| matches!(x, KrasValue::Text(_)));
+        let line = |space| match (is_mixed, space) {
+            (true, true) => RcDoc::space(),
+            (true, false) => RcDoc::nil(),
+            (false, _) => RcDoc::line_(),
+        };
+        let mut doc = RcDoc::nil();
+        let mut space = false;
+        for x in children {
+            doc = doc
+                .append(line(space || text(x, starts_with_space)))
+                .append(x.to_doc(opts, false));
+            space = text(x, ends_with_space);
+        }
+        doc.nest(opts.indent as isize).append(line(space))
+    }
+
+    // the decoded text of a string that is shown as a block of lines by RenderOptions::unescape: a string
+    // with escaped newlines. Keys and json output are always shown as written
+    fn text_block(&self, opts: &RenderOptions) -> Option<String> {
+        // a block without indentation can't be told from the lines around it: indent 0 keeps strings as is
+        let blocks = opts.unescape && opts.indent > 0 && opts.format == OutputFormat::Pretty;
+        match self {
+            KrasValue::Str((_, _, s)) if blocks && s.contains('\\') => {
+                self.str_value().filter(|value| value.contains('\n'))
+            }
+            _ => None,
+        }
+    }
+
+    // a colorized pretty document for the value
+    pub(crate) fn to_doc(&self, opts: &RenderOptions, is_key: bool) -> RcDoc<'_, ColorSpec> {
+        let nest = opts.indent as isize; // why tf _i_size?
+        match self {
+            // "a\nb" is shown as an indented block: |
+            KrasValue::Str((q, p, s)) => match self.text_block(opts).filter(|_| !is_key) {
+                Some(text) => {
+                    let red = ColorSpec::new().set_fg(Some(Color::Red)).clone();
+                    let lines = text
+                        .lines()
+                        .map(|line| RcDoc::as_string(line).annotate(red.clone()))
+                        .collect::<Vec<_>>();
+                    RcDoc::text("|")
+                        .annotate(ColorSpec::new().set_bold(true).clone())
                         .append(
-                            RcDoc::intersperse(it.iter().map(|x| x.to_doc(indent, false)), RcDoc::line_())
-                                .nest(nest)
-                                .append(Doc::line_()),
+                            RcDoc::hardline()
+                                .append(RcDoc::intersperse(lines, RcDoc::hardline()))
+                                .nest(nest),
                         )
-                        .group(),
-                )
-                .append(
-                    RcDoc::nil()
-                        .append(cl)
-                        .annotate(ColorSpec::new().set_bold(true).clone()),
-                ),
+                }
+                None => {
+                    let close = q.chars().rev().collect::<String>();
+                    RcDoc::as_string(format!("{p}{q}{s}{close}"))
+                        .annotate(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(is_key).clone())
+                }
+            },
+            KrasValue::Ident(s) => {
+                RcDoc::as_string(s).annotate(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(is_key).clone())
+            }
+            KrasValue::Atom(s) => {
+                RcDoc::as_string(s).annotate(ColorSpec::new().set_fg(Some(Color::Cyan)).set_bold(is_key).clone())
+            }
+            // logfmt has no brackets and is kept on its line: a=1 b=2
+            KrasValue::List((op, it, _)) if op.is_empty() => RcDoc::concat(it.iter().map(|x| {
+                match x {
+                    KrasValue::Pair((k, d, v, d2)) => RcDoc::nil()
+                        .append(k.to_doc(opts, true))
+                        .append(RcDoc::as_string(d))
+                        .append(v.to_doc(opts, false))
+                        .append(RcDoc::as_string(d2.clone().unwrap_or_default())),
+                    _ => x.to_doc(opts, false),
+                }
+            })),
+            KrasValue::List((op, it, cl)) => Self::list_doc(
+                RcDoc::text(op).annotate(ColorSpec::new().set_bold(true).clone()),
+                it,
+                cl,
+                opts,
+                false,
+            ),
             KrasValue::Pair((k, d, v, d2)) => {
+                // a block starts on the line of the key and is not followed by the list delimiter
+                let is_block = v.text_block(opts).is_some();
                 RcDoc::nil()
                     .append(
                         RcDoc::nil()
                             // key
-                            .append(k.to_doc(indent, true))
+                            .append(k.to_doc(opts, true))
                             // kv delim
                             .append(self.kv_spaces(d.to_string()))
                             .group(),
                     )
-                    .append(RcDoc::softline_())
+                    .append(if is_block { RcDoc::nil() } else { RcDoc::softline_() })
                     .nest(nest)
                     .append(
                         // value
                         RcDoc::nil()
-                            .append(v.to_doc(indent, false))
+                            .append(v.to_doc(opts, false))
                             // list delim
-                            .append(d2.clone().map_or(RcDoc::nil(), |d| self.kv_spaces(d)))
+                            .append(
+                                d2.clone()
+                                    .filter(|_| !is_block)
+                                    .map_or(RcDoc::nil(), |d| self.kv_spaces(d)),
+                            )
                             .group(),
                     )
             }
             .group(),
-            KrasValue::ListItem((v, d)) => RcDoc::nil()
-                .append(v.to_doc(indent, false))
-                .append(d.clone().map_or(RcDoc::nil(), |d| self.kv_spaces(d))),
+            KrasValue::ListItem((v, d)) => RcDoc::nil().append(v.to_doc(opts, false)).append(
+                d.clone()
+                    .filter(|_| v.text_block(opts).is_none())
+                    .map_or(RcDoc::nil(), |d| self.kv_spaces(d)),
+            ),
             KrasValue::Num(OrdF64(_n, r)) => {
                 RcDoc::as_string(r).annotate(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(is_key).clone())
             }
-            KrasValue::Constructor((id, args)) => RcDoc::nil()
-                .append(id.to_doc(indent, is_key))
-                .append(args.to_doc(indent, is_key))
-                .group(),
+            KrasValue::Constructor((id, args)) => match args.as_ref() {
+                // ruby inspect: the class name goes inside of the brackets, #<User id: 1>
+                KrasValue::List((op, it, cl)) if op == "#<" => {
+                    let open = RcDoc::text(op)
+                        .annotate(ColorSpec::new().set_bold(true).clone())
+                        .append(id.to_doc(opts, is_key));
+                    Self::list_doc(open, it, cl, opts, !it.is_empty())
+                }
+                _ => RcDoc::nil()
+                    .append(id.to_doc(opts, is_key))
+                    // edn tagged literal: #inst "2020-01-01"
+                    .append(if id.is_tag() { RcDoc::space() } else { RcDoc::nil() })
+                    .append(args.to_doc(opts, is_key))
+                    .group(),
+            },
+            KrasValue::Element((name, attrs, end, children)) => {
+                let bold = || ColorSpec::new().set_bold(true).clone();
+                let tag = |name| RcDoc::as_string(name).annotate(ColorSpec::new().set_fg(Some(Color::Blue)).clone());
+                let attrs = RcDoc::concat(attrs.iter().map(|a| RcDoc::line().append(a.to_doc(opts, false))));
+                let start = RcDoc::text("<")
+                    .annotate(bold())
+                    .append(tag(name))
+                    .append(attrs.nest(nest))
+                    .append(RcDoc::text(end).annotate(bold()))
+                    .group();
+                match children {
+                    None => start,
+                    Some(children) => start
+                        .append(Self::xml_children_doc(children, opts))
+                        .append(RcDoc::text("</").annotate(bold()))
+                        .append(tag(name))
+                        .append(RcDoc::text(">").annotate(bold())),
+                }
+            }
+            KrasValue::Text(s) => RcDoc::as_string(s.trim()),
+            // the encoding is shown like an edn tag: #base64 {"a": 1}
+            KrasValue::Decoded((enc, v)) => RcDoc::as_string(format!("#{enc}"))
+                .annotate(ColorSpec::new().set_dimmed(true).clone())
+                .append(RcDoc::space())
+                .append(v.to_doc(opts, is_key)),
+            KrasValue::Comment(s) => RcDoc::as_string(s)
+                .annotate(ColorSpec::new().set_dimmed(true).clone())
+                .append(if s.starts_with("/*") {
+                    RcDoc::space()
+                }
+                else {
+                    RcDoc::nil()
+                }),
+            KrasValue::Truncated => {
+                RcDoc::text("…").annotate(ColorSpec::new().set_fg(Some(Color::Magenta)).set_bold(true).clone())
+            }
             KrasValue::RawStr(s) => RcDoc::as_string(s),
             KrasValue::RawList(it) => RcDoc::nil()
                 .append(RcDoc::intersperse(
-                    it.iter().map(|x| x.to_doc(indent, false)),
+                    it.iter().map(|x| x.to_doc(opts, false)),
                     RcDoc::nil(),
                 ))
                 .group(),
@@ -256,20 +588,44 @@ impl KrasValue {
         .group()
     }
 
-    pub fn render(&self, indent: usize, min_len: usize, color_choice: ColorChoice) -> String {
+    /// Pretty-print the value in the format set by `opts`
+    pub fn render(&self, opts: &RenderOptions) -> String {
+        match opts.format {
+            OutputFormat::Pretty => self.render_doc(opts),
+            OutputFormat::Json => {
+                let mut losses = Vec::new();
+                let res = self.to_json(&mut losses);
+                for loss in losses {
+                    warn!("json: {loss}");
+                }
+                res.render_doc(opts)
+            }
+            OutputFormat::Yaml => {
+                let mut losses = Vec::new();
+                let res = self.render_yaml(opts, &mut losses);
+                for loss in losses {
+                    warn!("yaml: {loss}");
+                }
+                res
+            }
+        }
+    }
+
+    fn render_doc(&self, opts: &RenderOptions) -> String {
         match self {
             KrasValue::RawStr(s) => s.clone(),
-            KrasValue::RawList(items) => items.iter().map(|i| i.render(indent, min_len, color_choice)).collect(),
-            _ => {
-                let mut buffer = match color_choice {
-                    ColorChoice::Always | ColorChoice::Auto => termcolor::Buffer::ansi(),
-                    ColorChoice::Never => termcolor::Buffer::no_color(),
-                    _ => termcolor::Buffer::no_color(),
-                };
-                let doc = self.to_doc(indent, false);
-                doc.render_colored(min_len, &mut buffer).unwrap();
-                std::str::from_utf8(buffer.as_slice()).unwrap().to_string()
-            }
+            KrasValue::RawList(items) => items.iter().map(|i| i.render_doc(opts)).collect(),
+            _ => render_colored(self.to_doc(opts, false), opts),
         }
     }
 }
+
+pub(crate) fn render_colored(doc: RcDoc<'_, ColorSpec>, opts: &RenderOptions) -> String {
+    let mut buffer = match opts.color {
+        ColorChoice::Always | ColorChoice::Auto => termcolor::Buffer::ansi(),
+        ColorChoice::Never => termcolor::Buffer::no_color(),
+        _ => termcolor::Buffer::no_color(),
+    };
+    doc.render_colored(opts.line_width(), &mut buffer).unwrap();
+    std::str::from_utf8(buffer.as_slice()).unwrap().to_string()
+}
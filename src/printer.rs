@@ -4,7 +4,7 @@ use crossbeam::channel::Receiver;
 
 /*
 This object consumes lines from several threads. Each message is a
-(line_number, String)
+(line_number, Option<String>). None means that there is nothing to print for this line
 Messages are consumed in random order and put to a priority queue
 When the line number at top of the queue equals to next_line_num -
 it is popped from the queue and printed
@@ -15,20 +15,20 @@ pub struct Printer {
 }
 
 impl Printer {
-    pub fn new(receiver: Receiver<(usize, String)>) -> Self {
+    pub fn new(receiver: Receiver<(usize, Option<String>)>) -> Self {
         // just for statistics: how many lines max were in the queue?
         let mut max_qlen = 0;
         // number of the next line to print
         let mut next_line_num = 0;
         // a storage for lines that are to be printed
-        let mut output_queue = BinaryHeap::<Reverse<(usize, String)>>::new();
+        let mut output_queue = BinaryHeap::<Reverse<(usize, Option<String>)>>::new();
         let thread = std::thread::spawn(move || {
             let stdout = std::io::stdout();
             let mut stdout = stdout.lock();
             'recv: while let Ok((i, line)) = receiver.recv() {
                 max_qlen = usize::max(max_qlen, output_queue.len());
                 if i == next_line_num {
-                    if let Err(err) = writeln!(stdout, "{line}") {
+                    if let Err(err) = write_line(&mut stdout, &line) {
                         if err.kind() == std::io::ErrorKind::BrokenPipe {
                             debug!("write error {err}");
                         }
@@ -45,7 +45,7 @@ impl Printer {
 
                 if let Some(Reverse((i, line))) = output_queue.peek() {
                     if *i == next_line_num {
-                        if let Err(err) = writeln!(stdout, "{line}") {
+                        if let Err(err) = write_line(&mut stdout, line) {
                             if err.kind() == std::io::ErrorKind::BrokenPipe {
                                 debug!("write error {err}");
                             }
@@ -60,7 +60,7 @@ impl Printer {
                 }
             }
             while let Some(Reverse((_, line))) = output_queue.pop() {
-                if let Err(err) = writeln!(stdout, "{line}") {
+                if let Err(err) = write_line(&mut stdout, &line) {
                     if err.kind() == std::io::ErrorKind::BrokenPipe {
                         debug!("write error {err}");
                     }
@@ -79,3 +79,10 @@ impl Printer {
         self.thread.join().expect("join failed")
     }
 }
+
+fn write_line(out: &mut impl Write, line: &Option<String>) -> std::io::Result<()> {
+    match line {
+        Some(line) => writeln!(out, "{line}"),
+        None => Ok(()),
+    }
+}
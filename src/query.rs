@@ -0,0 +1,238 @@
+use std::{fmt, iter::FromIterator, str::FromStr};
+
+use pom::parser::*;
+
+use crate::pretty_value::*;
+
+/*
+A small subset of jq paths:
+.a.b      - value of key b inside the value of key a
+.["a b"]  - quoted key
+.[0]      - list item. Negative index counts from the end
+.* .[*]   - all values of a dict or list
+..a       - key a at any depth
+.         - the value itself
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(isize),
+    Wildcard,
+    // the value itself and all values nested in it
+    Descendants,
+}
+
+/// A jq-like path that selects values inside of the detected data
+///
+/// ```
+/// use kras::{parse_str, ParseOptions, Query, RenderOptions};
+///
+/// let query: Query = ".user.id".parse().unwrap();
+/// let value = parse_str(r#"got {"user": {"id": 5}}"#, &ParseOptions::new());
+/// let ids = value.select(&query);
+/// assert_eq!(ids[0].render(&RenderOptions::new()), "5");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query(Vec<Step>);
+
+/// An error returned when a [`Query`] can not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query: {}", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+fn key<'a>() -> Parser<'a, char, String> {
+    let key = is_a(|c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '$' || c == '@').repeat(1..);
+    key.map(String::from_iter)
+}
+
+fn quoted_key<'a>() -> Parser<'a, char, String> {
+    let escape_sequence = sym('\\') * any();
+    let string = sym('"') * (none_of("\\\"") | escape_sequence).repeat(0..) - sym('"');
+    string.map(String::from_iter)
+}
+
+fn index<'a>() -> Parser<'a, char, isize> {
+    let number = sym('-').opt() + one_of("0123456789").repeat(1..);
+    number.collect().map(String::from_iter).convert(|s| isize::from_str(&s))
+}
+
+fn bracket<'a>() -> Parser<'a, char, Step> {
+    let inner = sym('*').map(|_| Step::Wildcard) | index().map(Step::Index) | quoted_key().map(Step::Key);
+    sym('[') * inner - sym(']')
+}
+
+fn field<'a>() -> Parser<'a, char, Step> {
+    key().map(Step::Key) | sym('*').map(|_| Step::Wildcard) | bracket()
+}
+
+fn step<'a>() -> Parser<'a, char, Vec<Step>> {
+    let descendants = (sym('.') + sym('.')) * field().map(|s| vec![Step::Descendants, s]);
+    let dot = sym('.') * field().map(|s| vec![s]);
+    descendants | dot | bracket().map(|s| vec![s])
+}
+
+fn query<'a>() -> Parser<'a, char, Query> {
+    let steps = step().repeat(1..).map(|s| Query(s.into_iter().flatten().collect()));
+    (steps | sym('.').map(|_| Query(vec![]))) - end()
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.chars().collect::<Vec<_>>();
+        let res = query().parse(&input).map_err(|e| QueryError(format!("{s}: {e}")));
+        res
+    }
+}
+
+// strip the ListItem and Decoded wrappers and delimiters
+fn unwrap(val: &KrasValue) -> &KrasValue {
+    match val {
+        KrasValue::ListItem((v, _)) | KrasValue::Decoded((_, v)) => unwrap(v),
+        _ => val,
+    }
+}
+
+impl KrasValue {
+    fn key_matches(&self, key: &str) -> bool {
+        match self {
+            KrasValue::Str(_) => self.str_value().is_some_and(|s| s == key),
+            KrasValue::Atom(_) => self.atom_name().is_some_and(|s| s == key),
+            KrasValue::Ident(s) | KrasValue::Num(OrdF64(_, s)) => s == key,
+            KrasValue::List(_) => self.bracketed_key().is_some_and(|k| k.key_matches(key)),
+            _ => false,
+        }
+    }
+
+    // (key, value) of dict items, (None, value) of list items
+    fn children(&self) -> Vec<(Option<&KrasValue>, &KrasValue)> {
+        match self {
+            KrasValue::List((_, items, _)) => items
+                .iter()
+                .filter(|item| !item.is_comment())
+                .map(|item| match item {
+                    KrasValue::Pair((k, _, v, _)) => (Some(k.as_ref()), unwrap(v)),
+                    _ => (None, unwrap(item)),
+                })
+                .collect(),
+            KrasValue::Constructor((_, args)) => args.children(),
+            KrasValue::ListItem((v, _)) | KrasValue::Decoded((_, v)) => v.children(),
+            _ => vec![],
+        }
+    }
+
+    fn descendants<'a>(&'a self, res: &mut Vec<&'a KrasValue>) {
+        res.push(self);
+        for (_, v) in self.children() {
+            v.descendants(res)
+        }
+    }
+
+    fn select_step(&self, step: &Step) -> Vec<&KrasValue> {
+        match step {
+            Step::Key(key) => self
+                .children()
+                .into_iter()
+                .filter(|(k, _)| k.is_some_and(|k| k.key_matches(key)))
+                .map(|(_, v)| v)
+                .collect(),
+            Step::Index(i) => {
+                let items = self.children();
+                let i = if *i < 0 { items.len() as isize + i } else { *i };
+                match items.get(i as usize) {
+                    Some((None, v)) if i >= 0 => vec![*v],
+                    _ => vec![],
+                }
+            }
+            Step::Wildcard => self.children().into_iter().map(|(_, v)| v).collect(),
+            Step::Descendants => {
+                let mut res = Vec::new();
+                self.descendants(&mut res);
+                res
+            }
+        }
+    }
+
+    /// All values matching `query`. The query is applied to each detected value separately
+    pub fn select(&self, query: &Query) -> Vec<&KrasValue> {
+        let mut res = self.data().into_iter().map(unwrap).collect::<Vec<_>>();
+        for step in &query.0 {
+            res = res.into_iter().flat_map(|v| v.select_step(step)).collect();
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{options::RenderOptions, parse::parse_str, ParseOptions};
+
+    fn select(input: &str, query: &str) -> Vec<String> {
+        let val = parse_str(input, &ParseOptions::new());
+        let query = query.parse::<Query>().unwrap();
+        val.select(&query)
+            .iter()
+            .map(|v| v.render(&RenderOptions::new().indent(0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(".".parse(), Ok(Query(vec![])));
+        assert_eq!(
+            ".a[0]..b.*[*]".parse(),
+            Ok(Query(vec![
+                Step::Key("a".to_string()),
+                Step::Index(0),
+                Step::Descendants,
+                Step::Key("b".to_string()),
+                Step::Wildcard,
+                Step::Wildcard,
+            ]))
+        );
+        assert_eq!(
+            r#".["a \"b"][-1]"#.parse(),
+            Ok(Query(vec![Step::Key("a \"b".to_string()), Step::Index(-1)]))
+        );
+        assert!("a".parse::<Query>().is_err());
+        assert!(".a.".parse::<Query>().is_err());
+        assert!(".[x]".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let input = r#"1: {"user": {"id": 1, "tags": ["a", "b"]}} 2: {user: {id: 2, "tags": []}}"#;
+        let tests = [
+            (
+                ".",
+                vec![
+                    r#"{"user": {"id": 1, "tags": ["a", "b"]}}"#,
+                    r#"{user: {id: 2, "tags": []}}"#,
+                ],
+            ),
+            (".user.id", vec!["1", "2"]),
+            (".user.tags[0]", vec![r#""a""#]),
+            (".user.tags[-1]", vec![r#""b""#]),
+            (".user.tags[2]", vec![]),
+            (".user.tags[*]", vec![r#""a""#, r#""b""#]),
+            ("..id", vec!["1", "2"]),
+            (".user.*", vec!["1", r#"["a", "b"]"#, "2", "[]"]),
+            (r#".["user"]["id"]"#, vec!["1", "2"]),
+            (".missing", vec![]),
+        ];
+        for (query, expected) in tests {
+            assert_eq!(select(input, query), expected, "{query}");
+        }
+        assert_eq!(select("[Foo { a: 1 }, Bar(a=2)]", "..a"), vec!["1", "2"]);
+    }
+}
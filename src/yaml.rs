@@ -0,0 +1,200 @@
+use pretty::{
+    termcolor::{Color, ColorSpec},
+    *,
+};
+
+use crate::{
+    json::JsonLoss,
+    options::RenderOptions,
+    pretty_value::{render_colored, KrasValue, OrdF64},
+};
+
+// a string that can be written without quotes and will not be read back as a number, bool, null or date.
+// yaml 1.1 reads a lot of things starting with a digit as numbers and dates: 0x10, 0o17, 1_000, 1:20, 2024-01-02
+fn is_plain(s: &str) -> bool {
+    let first_ok = s
+        .chars()
+        .next()
+        .is_some_and(|c| (c.is_alphanumeric() && !c.is_ascii_digit()) || c == '_' || c == '/');
+    let chars_ok = s.chars().all(|c| c.is_alphanumeric() || "_-./@+() ".contains(c));
+    let special = matches!(
+        s.to_lowercase().as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n" | "null" | "~" | ".inf" | ".nan"
+    );
+    first_ok && chars_ok && !s.ends_with(' ') && !special && s.parse::<f64>().is_err()
+}
+
+// a json number as yaml 1.1 reads it: a float with an exponent needs a dot and a signed exponent, 1e40 is a
+// string for pyyaml. 1e40 is written as 1.0e+40
+fn yaml_number(r: &str) -> String {
+    match r.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => {
+            let dot = if mantissa.contains('.') { "" } else { ".0" };
+            let sign = if exp.starts_with(['+', '-']) { "" } else { "+" };
+            format!("{mantissa}{dot}e{sign}{exp}")
+        }
+        None => r.to_string(),
+    }
+}
+
+fn scalar(val: &KrasValue, is_key: bool) -> RcDoc<'static, ColorSpec> {
+    match val {
+        KrasValue::Str((_, _, s)) => {
+            // `s` is already escaped by to_json; a json string is a valid yaml double-quoted string
+            let s = if !s.contains('\\') && is_plain(s) {
+                s.to_string()
+            }
+            else {
+                format!("\"{s}\"")
+            };
+            RcDoc::as_string(s).annotate(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(is_key).clone())
+        }
+        KrasValue::Num(OrdF64(_, r)) => RcDoc::as_string(yaml_number(r))
+            .annotate(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(is_key).clone()),
+        KrasValue::Ident(s) => {
+            RcDoc::as_string(s).annotate(ColorSpec::new().set_fg(Some(Color::Blue)).set_bold(is_key).clone())
+        }
+        KrasValue::List((o, _, c)) => {
+            RcDoc::as_string(format!("{o}{c}")).annotate(ColorSpec::new().set_bold(true).clone())
+        }
+        KrasValue::ListItem((v, _)) => scalar(v, is_key),
+        _ => RcDoc::as_string(val.render(&RenderOptions::new().indent(0))),
+    }
+}
+
+fn is_collection(val: &KrasValue) -> bool {
+    match val {
+        KrasValue::List((_, items, _)) => !items.is_empty(),
+        KrasValue::ListItem((v, _)) => is_collection(v),
+        _ => false,
+    }
+}
+
+fn block(val: &KrasValue, indent: usize) -> RcDoc<'static, ColorSpec> {
+    match val {
+        KrasValue::List((o, items, _)) if !items.is_empty() && (o == "{" || o.is_empty()) => {
+            RcDoc::intersperse(items.iter().map(|item| block(item, indent)), RcDoc::hardline())
+        }
+        KrasValue::List((_, items, _)) if !items.is_empty() => RcDoc::intersperse(
+            items
+                .iter()
+                .map(|item| RcDoc::text("- ").append(block(item, indent).nest(2))),
+            RcDoc::hardline(),
+        ),
+        KrasValue::Pair((k, _, v, _)) => {
+            let key = scalar(k, true).append(RcDoc::text(":"));
+            if is_collection(v) {
+                key.append(RcDoc::hardline().append(block(v, indent)).nest(indent as isize))
+            }
+            else {
+                key.append(RcDoc::space()).append(scalar(v, false))
+            }
+        }
+        KrasValue::ListItem((v, _)) => block(v, indent),
+        _ => scalar(val, false),
+    }
+}
+
+impl KrasValue {
+    // a colorized block-style yaml document for the value. The value is converted with to_json first, every
+    // value that could not be represented exactly is reported to `losses`. Indent 0 gives flow-style yaml
+    pub(crate) fn to_yaml_doc(&self, indent: usize, losses: &mut Vec<JsonLoss>) -> RcDoc<'static, ColorSpec> {
+        let json = self.to_json(losses);
+        if indent == 0 {
+            return RcDoc::as_string(json.render(&RenderOptions::new().indent(0)));
+        }
+        block(&json, indent)
+    }
+
+    // every detected value becomes a separate yaml document, the raw text is kept around them
+    pub(crate) fn render_yaml(&self, opts: &RenderOptions, losses: &mut Vec<JsonLoss>) -> String {
+        match self {
+            KrasValue::RawStr(s) => s.clone(),
+            KrasValue::RawList(items) => {
+                let mut res = String::new();
+                for item in items {
+                    let is_raw = matches!(item, KrasValue::RawStr(_));
+                    if !res.is_empty() && (is_raw || !res.ends_with('\n')) {
+                        res.push('\n');
+                    }
+                    res.push_str(&item.render_yaml(opts, losses));
+                }
+                res
+            }
+            _ => {
+                let doc = RcDoc::text("---")
+                    .append(RcDoc::hardline())
+                    .append(self.to_yaml_doc(opts.indent, losses));
+                render_colored(doc, opts)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse::parse_str, OutputFormat, ParseOptions};
+
+    fn to_yaml(s: &str) -> String {
+        parse_str(s, &ParseOptions::new()).render(&RenderOptions::new().format(OutputFormat::Yaml))
+    }
+
+    #[test]
+    fn test_yaml() {
+        let tests = [
+            (
+                r#"{"a": 1, "b": [1, {"c": null, "d": []}], "e": {"f": "g h"}}"#,
+                "---\na: 1\nb:\n  - 1\n  - c: null\n    d: []\ne:\n  f: g h",
+            ),
+            (
+                "{'a': 'x: y', 'b': '1', 'c': True}",
+                "---\na: \"x: y\"\nb: \"1\"\nc: true",
+            ),
+            ("[[1, 2], [3]]", "---\n- - 1\n  - 2\n- - 3"),
+            ("x [1] y", "x \n---\n- 1\n y"),
+            ("{}", "---\n{}"),
+            (r#"['a"b']"#, "---\n- \"a\\\"b\""),
+            (
+                r#"{"d": "2024-01-02", "h": "0x10", "n": "1_000", "o": "0o17"}"#,
+                "---\nd: \"2024-01-02\"\nh: \"0x10\"\n\"n\": \"1_000\"\no: \"0o17\"",
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(to_yaml(input), expected);
+        }
+        let input = "[1e40, 1E-3, -2.5e4200, 1e00, 1.5, 10, 0x10]";
+        let expected = "---\n- 1.0e+40\n- 1.0e-3\n- -2.5e+4200\n- 1.0e+00\n- 1.5\n- 10\n- 16";
+        assert_eq!(to_yaml(input), expected);
+    }
+
+    #[test]
+    fn test_is_plain() {
+        for s in ["abc", "a b", "/usr/bin", "a.b-c"] {
+            assert!(is_plain(s), "{s}");
+        }
+        for s in [
+            "",
+            "1",
+            "1.5",
+            "true",
+            "No",
+            "null",
+            "a: b",
+            "a #b",
+            " a",
+            "a ",
+            "-a",
+            "[a]",
+            "2024-01-02",
+            "0x10",
+            "0o17",
+            "1_000",
+            "1:20",
+            "1e3",
+            "12ms",
+        ] {
+            assert!(!is_plain(s), "{s}");
+        }
+    }
+}